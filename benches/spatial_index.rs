@@ -0,0 +1,67 @@
+use a_bad_nights_sleep::spatial::SpatialIndex;
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use fastrand::Rng;
+
+/// Enemies scattered over an area roughly the size of the arena.
+fn enemies(count: u32) -> Vec<(Entity, Vec2)> {
+    let mut rng = Rng::with_seed(42);
+    (0..count)
+        .map(|i| {
+            let position = Vec2::new(rng.f32() - 0.5, rng.f32() - 0.5) * Vec2::new(1500.0, 800.0);
+            (Entity::from_raw(i), position)
+        })
+        .collect()
+}
+
+fn build_index(enemies: &[(Entity, Vec2)]) -> SpatialIndex {
+    let mut index = SpatialIndex::new(64.0);
+    for &(entity, position) in enemies {
+        index.insert(entity, position);
+    }
+    index
+}
+
+fn linear_nearest(enemies: &[(Entity, Vec2)], position: Vec2) -> Option<(Entity, Vec2)> {
+    enemies.iter().copied().min_by(|a, b| {
+        a.1.distance_squared(position)
+            .total_cmp(&b.1.distance_squared(position))
+    })
+}
+
+fn spatial_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_index");
+    let query = Vec2::new(100.0, -50.0);
+
+    for count in [1_000, 5_000, 20_000] {
+        let enemies = enemies(count);
+        let index = build_index(&enemies);
+
+        group.bench_with_input(BenchmarkId::new("build", count), &enemies, |b, enemies| {
+            b.iter(|| build_index(black_box(enemies)))
+        });
+        group.bench_with_input(BenchmarkId::new("nearest", count), &index, |b, index| {
+            b.iter(|| index.nearest(black_box(query)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("linear_nearest", count),
+            &enemies,
+            |b, enemies| b.iter(|| linear_nearest(black_box(enemies), black_box(query))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("k_nearest_10", count),
+            &index,
+            |b, index| b.iter(|| index.k_nearest(black_box(query), 10)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("within_radius_100", count),
+            &index,
+            |b, index| b.iter(|| index.within_radius(black_box(query), 100.0).count()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, spatial_index);
+criterion_main!(benches);
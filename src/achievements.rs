@@ -0,0 +1,339 @@
+//! Achievements, defined in `assets/achievements.ron`. Gameplay only sends events;
+//! this module keeps count of what happened and checks every condition in one place.
+
+use std::path::{Path, PathBuf};
+
+use bevy::{color::palettes::tailwind, prelude::*};
+use bevy_common_assets::ron::RonAssetPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    day::{new_day, UpgradeBought},
+    enemy::EnemyDiedEvent,
+    journal::{load_profile_file, save_profile_file},
+    night::{WakeCause, WokeUp},
+    player::{PlayerDamaged, PlayerStats},
+    replay::Replay,
+    GameState,
+};
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<AchievementList>::new(&[
+            "achievements.ron",
+        ]));
+        app.add_event::<AchievementUnlocked>();
+        app.init_resource::<AchievementDefs>();
+        app.init_resource::<RunProgress>();
+        app.insert_resource(AchievementProgress::load(ACHIEVEMENTS_PATH));
+
+        app.add_systems(OnEnter(GameState::DayTime), start_run.before(new_day));
+        app.add_systems(OnEnter(GameState::NightTime), start_night);
+        // Replayed runs were counted when they were played.
+        app.add_systems(
+            OnExit(GameState::NightTime),
+            finish_night.run_if(not(resource_exists::<Replay>)),
+        );
+        app.add_systems(OnEnter(GameState::GameWon), win_run);
+        // Before state transitions, so the purchases are in when the night starts.
+        app.add_systems(PreUpdate, track_purchases);
+        app.add_systems(
+            FixedUpdate,
+            (track_kills, track_damage)
+                .run_if(in_state(GameState::NightTime).and(not(resource_exists::<Replay>))),
+        );
+        app.add_systems(Startup, spawn_toast_stack);
+        app.add_systems(
+            Update,
+            (
+                check_achievements.run_if(not(resource_exists::<Replay>)),
+                spawn_toasts,
+                expire_toasts,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Where unlocked achievements are kept.
+pub const ACHIEVEMENTS_PATH: &str = "achievements.json";
+/// Seconds a toast stays on screen.
+const TOAST_DURATION: f32 = 4.0;
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Condition {
+    /// Beat this many monsters in a single night.
+    KillsInNight(u32),
+    /// Beat this many monsters over every run ever played.
+    TotalKills(u32),
+    /// Sleep through a whole night.
+    FullNight,
+    /// Sleep through a whole night without getting hurt.
+    UntouchedNight,
+    /// Take this much damage in a single night and still sleep through it.
+    DamageInFullNight(f32),
+    /// Buy this many upgrades in a single run.
+    UpgradesInRun(u32),
+    Win,
+    /// Win without ever buying this upgrade.
+    WinWithout(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AchievementDef {
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct AchievementList {
+    pub achievements: Vec<AchievementDef>,
+}
+
+#[derive(Resource)]
+pub struct AchievementDefs(pub Handle<AchievementList>);
+
+impl FromWorld for AchievementDefs {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<AssetServer>().load("achievements.ron"))
+    }
+}
+
+/// What has happened so far in the current run.
+#[derive(Resource, Default)]
+pub struct RunProgress {
+    pub night_kills: u32,
+    pub night_damage: f32,
+    /// How the last night ended, once it has.
+    pub night_end: Option<WakeCause>,
+    pub purchases: Vec<&'static str>,
+    pub won: bool,
+}
+
+/// Unlocked achievements and the counts that carry over between runs.
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct AchievementProgress {
+    pub unlocked: Vec<String>,
+    pub total_kills: u32,
+    /// Where the progress is saved, or `None` to keep it in memory only.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl AchievementProgress {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self {
+            path: Some(path.to_owned()),
+            ..load_profile_file(path)
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            save_profile_file(path, self);
+        }
+    }
+
+    pub fn is_unlocked(&self, name: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == name)
+    }
+}
+
+impl Condition {
+    pub fn is_met(&self, run: &RunProgress, progress: &AchievementProgress) -> bool {
+        let full_night = run.night_end == Some(WakeCause::FullNight);
+        match self {
+            Condition::KillsInNight(kills) => run.night_kills >= *kills,
+            Condition::TotalKills(kills) => progress.total_kills >= *kills,
+            Condition::FullNight => full_night,
+            Condition::UntouchedNight => full_night && run.night_damage == 0.0,
+            Condition::DamageInFullNight(damage) => full_night && run.night_damage >= *damage,
+            Condition::UpgradesInRun(count) => run.purchases.len() as u32 >= *count,
+            Condition::Win => run.won,
+            Condition::WinWithout(upgrade) => {
+                run.won && !run.purchases.iter().any(|name| name == upgrade)
+            }
+        }
+    }
+}
+
+/// Sent when an achievement is unlocked for the first time.
+#[derive(Event)]
+pub struct AchievementUnlocked {
+    pub name: String,
+    pub description: String,
+}
+
+fn start_run(mut run: ResMut<RunProgress>, player_stats: Res<PlayerStats>) {
+    if player_stats.day == 0 {
+        *run = RunProgress::default();
+    }
+}
+
+fn start_night(mut run: ResMut<RunProgress>) {
+    run.night_kills = 0;
+    run.night_damage = 0.0;
+    run.night_end = None;
+}
+
+fn finish_night(
+    mut run: ResMut<RunProgress>,
+    progress: Res<AchievementProgress>,
+    mut woke_up_reader: EventReader<WokeUp>,
+) {
+    run.night_end = woke_up_reader.read().last().map(|woke_up| woke_up.0);
+    // The kill count changes all night, so it's only saved once it's over.
+    progress.save();
+}
+
+fn win_run(mut run: ResMut<RunProgress>) {
+    run.won = true;
+}
+
+fn track_purchases(
+    mut run: ResMut<RunProgress>,
+    mut upgrade_bought_reader: EventReader<UpgradeBought>,
+) {
+    for event in upgrade_bought_reader.read() {
+        run.purchases.push(event.name);
+    }
+}
+
+fn track_kills(
+    mut run: ResMut<RunProgress>,
+    mut progress: ResMut<AchievementProgress>,
+    mut enemy_died_event_reader: EventReader<EnemyDiedEvent>,
+) {
+    for event in enemy_died_event_reader.read() {
+        if event.killed {
+            run.night_kills += 1;
+            progress.total_kills += 1;
+        }
+    }
+}
+
+fn track_damage(
+    mut run: ResMut<RunProgress>,
+    mut player_damaged_reader: EventReader<PlayerDamaged>,
+) {
+    for event in player_damaged_reader.read() {
+        run.night_damage += event.amount;
+    }
+}
+
+fn check_achievements(
+    run: Res<RunProgress>,
+    mut progress: ResMut<AchievementProgress>,
+    defs: Res<AchievementDefs>,
+    lists: Res<Assets<AchievementList>>,
+    mut unlocked_writer: EventWriter<AchievementUnlocked>,
+) {
+    if !run.is_changed() && !progress.is_changed() {
+        return;
+    }
+    let Some(list) = lists.get(&defs.0) else {
+        return;
+    };
+
+    let mut unlocked_any = false;
+    for achievement in &list.achievements {
+        if progress.is_unlocked(&achievement.name) || !achievement.condition.is_met(&run, &progress)
+        {
+            continue;
+        }
+
+        info!("Achievement unlocked: {}", achievement.name);
+        progress.unlocked.push(achievement.name.clone());
+        unlocked_writer.send(AchievementUnlocked {
+            name: achievement.name.clone(),
+            description: achievement.description.clone(),
+        });
+        unlocked_any = true;
+    }
+
+    if unlocked_any {
+        progress.save();
+    }
+}
+
+/// Holds the toasts, so they stack instead of overlapping.
+#[derive(Component)]
+struct ToastStack;
+
+#[derive(Component)]
+struct Toast(Timer);
+
+fn spawn_toast_stack(mut commands: Commands) {
+    commands.spawn((
+        ToastStack,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Px(10.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(5.0),
+            ..default()
+        },
+        PickingBehavior::IGNORE,
+    ));
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut unlocked_reader: EventReader<AchievementUnlocked>,
+    stack_query: Query<Entity, With<ToastStack>>,
+) {
+    let Ok(stack) = stack_query.get_single() else {
+        return;
+    };
+
+    for event in unlocked_reader.read() {
+        commands.entity(stack).with_children(|stack| {
+            stack
+                .spawn((
+                    Toast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                        ..default()
+                    },
+                    BackgroundColor(tailwind::GRAY_900.with_alpha(0.8).into()),
+                    PickingBehavior::IGNORE,
+                ))
+                .with_children(|toast| {
+                    toast.spawn((
+                        Text::new(format!("Achievement unlocked: {}", event.name)),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(tailwind::AMBER_300.into()),
+                    ));
+                    toast.spawn((
+                        Text::new(event.description.clone()),
+                        TextFont {
+                            font_size: 10.0,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    mut toast_query: Query<(Entity, &mut Toast)>,
+    time: Res<Time<Real>>,
+) {
+    for (entity, mut toast) in toast_query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
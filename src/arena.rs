@@ -0,0 +1,117 @@
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, RigidBody};
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{game_assets::GameAssets, night::Level, GameLayer, GameState};
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<Arena>::new(&["arena.ron"]));
+        app.add_systems(
+            FixedUpdate,
+            spawn_arena_walls.run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// The walls of the bedroom the night takes place in.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone)]
+pub struct Arena {
+    pub wall_thickness: f32,
+    /// Corners of the room in counter-clockwise order.
+    pub outline: Vec<(f32, f32)>,
+}
+
+impl Arena {
+    pub fn corners(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.outline.iter().map(|&(x, y)| Vec2::new(x, y))
+    }
+
+    /// The wall segments of the room, as pairs of corners.
+    pub fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.corners().zip(self.corners().cycle().skip(1))
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.corners()
+            .fold(Rect::EMPTY, |bounds, corner| bounds.union_point(corner))
+    }
+
+    /// Whether `point` is inside the room, using the even-odd rule.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// A random point inside the room, at least `margin` away from the bounding box.
+    pub fn random_point(&self, rng: &mut Rng, margin: f32) -> Vec2 {
+        let bounds = self.bounds().inflate(-margin);
+        for _ in 0..100 {
+            let point = Vec2::new(
+                bounds.min.x + rng.f32() * bounds.width(),
+                bounds.min.y + rng.f32() * bounds.height(),
+            );
+            if self.contains(point) {
+                return point;
+            }
+        }
+        bounds.center()
+    }
+}
+
+#[derive(Component)]
+pub struct ArenaWall;
+
+fn spawn_arena_walls(
+    mut commands: Commands,
+    walls: Query<(), With<ArenaWall>>,
+    arenas: Res<Assets<Arena>>,
+    level: Res<Level>,
+    game_assets: Res<GameAssets>,
+) {
+    if !walls.is_empty() {
+        return;
+    }
+
+    let Some(arena) = arenas.get(&level.arena) else {
+        return;
+    };
+
+    for (a, b) in arena.edges() {
+        let along = b - a;
+        // The room is counter-clockwise, so the outside is to the right of each edge.
+        let outward = Vec2::new(along.y, -along.x).normalize();
+        let center = (a + b) / 2.0 + outward * arena.wall_thickness / 2.0;
+
+        // The wall mesh and collider are unit squares scaled to the wall's size.
+        commands.spawn((
+            ArenaWall,
+            StateScoped(GameState::NightTime),
+            Transform {
+                translation: center.extend(-1.0),
+                rotation: Quat::from_rotation_z(along.to_angle()),
+                scale: Vec3::new(
+                    along.length() + arena.wall_thickness * 2.0,
+                    arena.wall_thickness,
+                    1.0,
+                ),
+            },
+            RigidBody::Static,
+            Collider::rectangle(1.0, 1.0),
+            CollisionLayers::new(GameLayer::Default, LayerMask::ALL),
+            Mesh2d(game_assets.wall_mesh.clone()),
+            MeshMaterial2d(game_assets.wall_material.clone()),
+        ));
+    }
+}
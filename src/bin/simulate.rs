@@ -0,0 +1,149 @@
+//! Plays complete runs with the bot, without a window, and prints a report for
+//! every night. Meant for checking balance changes, including on CI machines
+//! without a GPU.
+//!
+//! Usage: `cargo run --bin simulate -- [--runs N] [--format csv|json] [--strategy NAME]
+//! [--difficulty NAME]`
+
+use std::process::ExitCode;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use a_bad_nights_sleep::{
+    bot::{strategy_by_name, Autopilot, STRATEGIES},
+    difficulty::{Difficulty, Preset},
+    headless::{headless_app, preload_level_assets, FRAME},
+    journal::DreamJournal,
+    night::WakeCause,
+    stats::{NightReport, RunStats},
+    GameState,
+};
+
+/// Gives up on a run that somehow never ends, which is more than 7 nights of a
+/// minute each.
+const MAX_SIMULATED_SECONDS: f32 = 60.0 * 60.0;
+
+#[derive(Serialize)]
+struct RunReport {
+    run: u32,
+    strategy: &'static str,
+    difficulty: &'static str,
+    outcome: &'static str,
+    nights: Vec<NightReport>,
+}
+
+enum Format {
+    Csv,
+    Json,
+}
+
+fn main() -> ExitCode {
+    let mut runs = 10;
+    let mut format = Format::Csv;
+    let mut strategy = "kiter".to_string();
+    let mut preset = Preset::Normal;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--runs", Some(value)) => match value.parse() {
+                Ok(value) => runs = value,
+                Err(_) => return usage(),
+            },
+            ("--format", Some(value)) if value == "csv" => format = Format::Csv,
+            ("--format", Some(value)) if value == "json" => format = Format::Json,
+            ("--strategy", Some(value)) if STRATEGIES.contains(&value.as_str()) => strategy = value,
+            ("--difficulty", Some(value)) => match Preset::from_name(&value) {
+                Some(value) => preset = value,
+                None => return usage(),
+            },
+            _ => return usage(),
+        }
+    }
+
+    let reports: Vec<_> = (0..runs)
+        .map(|run| simulate_run(run, &strategy, preset))
+        .collect();
+    match format {
+        Format::Csv => print_csv(&reports),
+        Format::Json => match serde_json::to_string_pretty(&reports) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("Failed to write JSON: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!(
+        "Usage: simulate [--runs N] [--format csv|json] [--strategy {}] [--difficulty {}]",
+        STRATEGIES.join("|"),
+        Preset::ALL
+            .iter()
+            .map(|preset| preset.name())
+            .collect::<Vec<_>>()
+            .join("|")
+    );
+    ExitCode::FAILURE
+}
+
+fn simulate_run(run: u32, strategy: &str, preset: Preset) -> RunReport {
+    let mut app = headless_app();
+    app.world_mut().resource_mut::<DreamJournal>().difficulty = Difficulty::preset(preset);
+    let strategy = strategy_by_name(strategy).expect("strategy names are checked up front");
+    let name = strategy.name();
+    app.insert_resource(Autopilot::new(strategy));
+    if !preload_level_assets(&mut app) {
+        eprintln!("Run {run}: level data didn't load");
+    }
+
+    let mut outcome = "timeout";
+    for _ in 0..(MAX_SIMULATED_SECONDS / FRAME) as u32 {
+        app.update();
+        match app.world().resource::<State<GameState>>().get() {
+            GameState::GameWon => outcome = "won",
+            GameState::GameOver => outcome = "lost",
+            _ => continue,
+        }
+        break;
+    }
+
+    RunReport {
+        run,
+        strategy: name,
+        difficulty: preset.name(),
+        outcome,
+        nights: app.world().resource::<RunStats>().nights.clone(),
+    }
+}
+
+fn print_csv(reports: &[RunReport]) {
+    println!("run,strategy,difficulty,outcome,day,sleep_duration,sleep_intensity,survival_time,rest_earned,damage_taken,kills,died,woke_up,rest_spent,upgrades");
+    for report in reports {
+        for night in &report.nights {
+            println!(
+                "{},{},{},{},{},{},{},{:.2},{},{},{},{},{},{},{}",
+                report.run,
+                report.strategy,
+                report.difficulty,
+                report.outcome,
+                night.day,
+                night.sleep_duration,
+                night.sleep_intensity,
+                night.survival_time,
+                night.rest_earned,
+                night.damage_taken,
+                night.kills,
+                night.died,
+                night.woke_up.map_or("", WakeCause::name),
+                night.rest_spent,
+                night.upgrades.join(";"),
+            );
+        }
+    }
+}
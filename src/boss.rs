@@ -0,0 +1,405 @@
+use std::time::Duration;
+
+use avian2d::prelude::{Collider, Collision, CollisionLayers, LinearVelocity, RigidBody, Sensor};
+use bevy::{color::palettes::tailwind, prelude::*};
+
+use crate::{
+    arena::Arena,
+    camera::ScreenShake,
+    campaign::{Campaign, DayRule},
+    difficulty::Difficulty,
+    enemy::{
+        spawn_regular_enemy, Enemy, EnemyDiedEvent, EnemyHealth, EnemyProjectile, EnemyRules,
+        EnemyType,
+    },
+    game_assets::{GameAssets, BOSS_PROJECTILE_RADIUS},
+    night::{GameRng, Level, LevelState},
+    player::{NightPlayer, PlayerDamaged, PlayerStats},
+    sleep::SleepPhase,
+    timed_entity::Timed,
+    GameLayer, GameState,
+};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BossEncounter>();
+        app.add_systems(OnEnter(GameState::NightTime), reset_encounter);
+        app.add_systems(OnExit(GameState::NightTime), finish_encounter);
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_boss,
+                boss_phases,
+                boss_attacks,
+                boss_contact_damage,
+                handle_boss_death,
+                update_boss_hud,
+            )
+                .chain()
+                .run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// Tracks the boss encounter of the current night.
+#[derive(Resource, Default)]
+pub struct BossEncounter {
+    pub spawned: bool,
+    pub defeated: bool,
+}
+
+#[derive(Component)]
+#[require(Enemy)]
+pub struct Boss {
+    pub max_health: f32,
+    pub phase: usize,
+    pub last_attack: f32,
+    pub attack_index: usize,
+    pub charging_until: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BossAttack {
+    Charge,
+    Summon,
+    Ring,
+}
+
+/// The attacks used in each phase, cycled through in order.
+const PHASE_ATTACKS: &[&[BossAttack]] = &[
+    &[BossAttack::Charge],
+    &[BossAttack::Charge, BossAttack::Summon],
+    &[
+        BossAttack::Charge,
+        BossAttack::Ring,
+        BossAttack::Summon,
+        BossAttack::Ring,
+    ],
+];
+
+#[derive(Component)]
+struct BossHealthBar;
+
+fn reset_encounter(mut encounter: ResMut<BossEncounter>) {
+    *encounter = BossEncounter::default();
+}
+
+fn finish_encounter(encounter: Res<BossEncounter>, mut player_stats: ResMut<PlayerStats>) {
+    if encounter.spawned && !player_stats.died {
+        info!("Boss encounter cleared");
+        player_stats.boss_cleared = true;
+    }
+}
+
+fn spawn_boss(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut encounter: ResMut<BossEncounter>,
+    level_state: Res<LevelState>,
+    player_stats: Res<PlayerStats>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    rules: Res<Assets<EnemyRules>>,
+    arenas: Res<Assets<Arena>>,
+    level: Res<Level>,
+    player_query: Query<&GlobalTransform, With<NightPlayer>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let boss_night = campaign
+        .day(player_stats.day, &difficulty)
+        .has(DayRule::Boss);
+    if encounter.spawned || !boss_night {
+        return;
+    }
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+    let rules = &rules.boss;
+    let Some(boss_mesh) = &game_assets.boss_mesh else {
+        return;
+    };
+
+    // The boss arrives for the last part of the night.
+    let arrival = (player_stats.sleep_duration - rules.duration).max(0.0);
+    if level_state.timer.elapsed_secs() < arrival {
+        return;
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let Some(arena) = arenas.get(&level.arena) else {
+        return;
+    };
+
+    // Appear some distance away from the player, but inside the room.
+    let player_pos = player_transform.translation().truncate();
+    let pos = (0..10)
+        .map(|_| player_pos + Vec2::from_angle(rng.f32() * std::f32::consts::TAU) * 400.0)
+        .find(|&pos| arena.contains(pos))
+        .unwrap_or_else(|| arena.random_point(&mut rng, rules.radius));
+
+    info!("Insomnia appears");
+    encounter.spawned = true;
+
+    let health = rules.health * difficulty.enemy_health;
+    commands.spawn((
+        Boss {
+            max_health: health,
+            phase: 0,
+            last_attack: level_state.timer.elapsed_secs(),
+            attack_index: 0,
+            charging_until: 0.0,
+        },
+        EnemyType::Boss,
+        EnemyHealth::new(health),
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(pos.extend(0.0)),
+        Mesh2d(boss_mesh.clone()),
+        MeshMaterial2d(game_assets.boss_materials[0].clone()),
+        Collider::circle(rules.radius),
+        CollisionLayers::new(GameLayer::Enemy, [GameLayer::Default, GameLayer::Player]),
+        RigidBody::Dynamic,
+        LinearVelocity::default(),
+    ));
+
+    commands
+        .spawn((
+            StateScoped(GameState::NightTime),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Percent(20.0),
+                width: Val::Percent(60.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                Text::new("Insomnia"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+            hud.spawn((
+                Node {
+                    height: Val::Px(12.0),
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(tailwind::GRAY_700.into()),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    BossHealthBar,
+                    Node {
+                        height: Val::Percent(100.0),
+                        width: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(tailwind::PURPLE_500.into()),
+                ));
+            });
+        });
+}
+
+fn boss_phases(
+    game_assets: Res<GameAssets>,
+    mut shake: ResMut<ScreenShake>,
+    mut boss_query: Query<(&mut Boss, &EnemyHealth, &mut MeshMaterial2d<ColorMaterial>)>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+) {
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    for (mut boss, health, mut material) in boss_query.iter_mut() {
+        let fraction = **health / boss.max_health;
+        let phase = rules
+            .boss
+            .phase_thresholds
+            .iter()
+            .filter(|&&threshold| fraction <= threshold)
+            .count()
+            .min(PHASE_ATTACKS.len() - 1);
+
+        if phase > boss.phase {
+            info!("Insomnia enters phase {}", phase + 1);
+            boss.phase = phase;
+            boss.attack_index = 0;
+            shake.add_trauma(0.8);
+            if let Some(phase_material) = game_assets.boss_materials.get(phase) {
+                material.0 = phase_material.clone();
+            }
+        }
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut shake: ResMut<ScreenShake>,
+    mut boss_query: Query<(&mut Boss, &mut LinearVelocity, &GlobalTransform)>,
+    player_query: Query<&GlobalTransform, With<NightPlayer>>,
+    level_state: Res<LevelState>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+    difficulty: Res<Difficulty>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    let cur_time = level_state.timer.elapsed_secs();
+
+    for (mut boss, mut velocity, boss_transform) in boss_query.iter_mut() {
+        if cur_time < boss.charging_until {
+            continue;
+        }
+
+        let boss_pos = boss_transform.translation().truncate();
+        let direction = (player_transform.translation().truncate() - boss_pos).normalize_or_zero();
+        velocity.0 = direction * rules.boss.speed;
+
+        // Later phases attack more often.
+        let interval = rules.boss.attack_interval / (1.0 + boss.phase as f32 * 0.25);
+        if boss.last_attack + interval > cur_time {
+            continue;
+        }
+        boss.last_attack = cur_time;
+
+        let attacks = PHASE_ATTACKS[boss.phase];
+        let attack = attacks[boss.attack_index % attacks.len()];
+        boss.attack_index += 1;
+        debug!("Insomnia attacks: {attack:?}");
+
+        match attack {
+            BossAttack::Charge => {
+                velocity.0 = direction * rules.boss.charge_speed;
+                boss.charging_until = cur_time + rules.boss.charge_duration;
+                shake.add_trauma(0.3);
+            }
+            BossAttack::Summon => {
+                for i in 0..rules.boss.minions {
+                    let angle = i as f32 / rules.boss.minions as f32 * std::f32::consts::TAU;
+                    let offset = Vec2::from_angle(angle);
+                    spawn_regular_enemy(
+                        &mut commands,
+                        EnemyType::Basic,
+                        boss_pos + offset * (rules.boss.radius + 20.0),
+                        &game_assets,
+                        &difficulty,
+                    )
+                    .insert(LinearVelocity(offset * rules.base_speed));
+                }
+            }
+            BossAttack::Ring => {
+                shake.add_trauma(0.4);
+                for i in 0..rules.boss.ring_projectiles {
+                    let angle =
+                        i as f32 / rules.boss.ring_projectiles as f32 * std::f32::consts::TAU;
+                    let offset = Vec2::from_angle(angle);
+                    commands.spawn((
+                        EnemyProjectile {
+                            damage: rules.boss.projectile_damage,
+                        },
+                        StateScoped(GameState::NightTime),
+                        Timed(4.0),
+                        Transform::from_translation(
+                            (boss_pos + offset * rules.boss.radius).extend(0.0),
+                        ),
+                        Mesh2d(game_assets.boss_projectile_mesh.clone()),
+                        Collider::circle(BOSS_PROJECTILE_RADIUS),
+                        Sensor,
+                        CollisionLayers::new(
+                            GameLayer::EnemyProjectile,
+                            [GameLayer::Default, GameLayer::Player],
+                        ),
+                        RigidBody::Dynamic,
+                        MeshMaterial2d(game_assets.boss_projectile_material.clone()),
+                        LinearVelocity(offset * rules.boss.projectile_speed),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn boss_contact_damage(
+    mut collision_event_reader: EventReader<Collision>,
+    boss_query: Query<Entity, With<Boss>>,
+    mut player_query: Query<(Entity, &mut NightPlayer)>,
+    mut shake: ResMut<ScreenShake>,
+    mut player_damaged_writer: EventWriter<PlayerDamaged>,
+    phase: Res<SleepPhase>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+) {
+    let Ok((player_entity, mut player)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    for Collision(contacts) in collision_event_reader.read() {
+        if !contacts.collision_started() {
+            continue;
+        }
+        let involves_boss =
+            boss_query.contains(contacts.entity1) || boss_query.contains(contacts.entity2);
+        let involves_player =
+            contacts.entity1 == player_entity || contacts.entity2 == player_entity;
+        if involves_boss && involves_player {
+            let amount = rules.boss.contact_damage * phase.damage_taken();
+            player.health -= amount;
+            player_damaged_writer.send(PlayerDamaged { amount });
+            shake.add_trauma(0.6);
+        }
+    }
+}
+
+fn handle_boss_death(
+    mut encounter: ResMut<BossEncounter>,
+    mut enemy_died_event_reader: EventReader<EnemyDiedEvent>,
+    mut level_state: ResMut<LevelState>,
+    player_stats: Res<PlayerStats>,
+) {
+    for event in enemy_died_event_reader.read() {
+        if let EnemyType::Boss = event.enemy_type {
+            info!("Insomnia defeated");
+            encounter.defeated = true;
+            // Defeating the boss ends the night right away.
+            level_state
+                .timer
+                .set_elapsed(Duration::from_secs_f32(player_stats.sleep_duration));
+        }
+    }
+}
+
+fn update_boss_hud(
+    mut bar_query: Query<&mut Node, With<BossHealthBar>>,
+    boss_query: Query<(&Boss, &EnemyHealth)>,
+) {
+    let Ok(mut node) = bar_query.get_single_mut() else {
+        return;
+    };
+
+    let percent = boss_query
+        .get_single()
+        .map(|(boss, health)| 100.0 * (**health / boss.max_health).max(0.0))
+        .unwrap_or(0.0);
+    node.width = Val::Percent(percent);
+}
@@ -0,0 +1,367 @@
+//! An autopilot that can play the game on its own, for balance simulations, demos
+//! and tests. It feeds the character controller the same movement actions as the
+//! keyboard, and spends rest in the shop during the day.
+//!
+//! How it plays is decided by a [`BotStrategy`], so different strategies can be
+//! compared by simulating runs with each of them.
+
+use bevy::prelude::*;
+
+use crate::{
+    campaign::{Campaign, DayRule},
+    character::{CharacterSet, MovementAction},
+    day::{purchase, Upgrade, UpgradeBought, UPGRADES},
+    difficulty::Difficulty,
+    layout::LevelLayout,
+    pickup::Pickup,
+    player::{NightPlayer, PlayerStats},
+    spatial::EnemyIndex,
+    GameState,
+};
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopilot>();
+        app.add_systems(
+            Update,
+            go_shopping.run_if(in_state(GameState::DayTime).and(autopilot_enabled)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            pilot
+                .in_set(CharacterSet::Input)
+                .run_if(in_state(GameState::NightTime).and(autopilot_enabled)),
+        );
+
+        #[cfg(feature = "dev")]
+        app.add_systems(Update, toggle_autopilot);
+    }
+}
+
+/// How far away the bot notices enemies.
+const SIGHT_RADIUS: f32 = 250.0;
+
+/// What the bot can see of the night when deciding where to go.
+pub struct NightView {
+    pub position: Vec2,
+    /// Enemies within [`SIGHT_RADIUS`], closest first.
+    pub enemies: Vec<Vec2>,
+    /// Pickups within [`SIGHT_RADIUS`], closest first.
+    pub pickups: Vec<Vec2>,
+    /// Where the night started, usually the most open part of the room.
+    pub home: Vec2,
+}
+
+impl NightView {
+    /// A direction away from the enemies, weighted so that close enemies and
+    /// swarms count the most.
+    pub fn away_from_enemies(&self) -> Vec2 {
+        self.enemies
+            .iter()
+            .map(|&enemy| {
+                let away = self.position - enemy;
+                let distance = away.length().max(1.0);
+                away / (distance * distance)
+            })
+            .sum::<Vec2>()
+            .normalize_or_zero()
+    }
+
+    /// A pull back towards home, stronger the further away the bot has gone.
+    pub fn towards_home(&self) -> Vec2 {
+        let home = self.home - self.position;
+        home.normalize_or_zero() * (home.length() / 1000.0).min(0.5)
+    }
+}
+
+/// Decides how the autopilot plays.
+pub trait BotStrategy: Send + Sync + 'static {
+    fn name(&self) -> &'static str;
+
+    /// The direction to move in this frame, or zero to stand still.
+    fn steer(&mut self, view: &NightView) -> Vec2;
+
+    /// The next upgrade to buy, or `None` when done shopping for the day.
+    fn shop(
+        &mut self,
+        player_stats: &PlayerStats,
+        difficulty: &Difficulty,
+    ) -> Option<&'static Upgrade>;
+}
+
+/// The first upgrade on `list` that the player can afford at the difficulty's prices.
+pub fn first_affordable(
+    list: &[&str],
+    player_stats: &PlayerStats,
+    difficulty: &Difficulty,
+) -> Option<&'static Upgrade> {
+    list.iter()
+        .filter_map(|name| UPGRADES.iter().find(|upgrade| upgrade.name == *name))
+        .find(|upgrade| difficulty.price(upgrade.cost) <= player_stats.rest)
+}
+
+/// Keeps enemies just inside shooting range and circles around them, so the
+/// automatic shots land while the enemies never quite catch up.
+#[derive(Default)]
+pub struct Kiter;
+
+impl Kiter {
+    const TOO_CLOSE: f32 = 50.0;
+    const SHOOTING_RANGE: f32 = 95.0;
+}
+
+impl BotStrategy for Kiter {
+    fn name(&self) -> &'static str {
+        "kiter"
+    }
+
+    fn steer(&mut self, view: &NightView) -> Vec2 {
+        let Some(&nearest) = view.enemies.first() else {
+            // Nothing to fight, so tidy up the pickups or wait at home.
+            return match view.pickups.first() {
+                Some(&pickup) => pickup - view.position,
+                None => view.towards_home(),
+            };
+        };
+
+        let to_enemy = nearest - view.position;
+        let distance = to_enemy.length();
+        let direction = if distance < Self::TOO_CLOSE {
+            view.away_from_enemies()
+        } else if distance < Self::SHOOTING_RANGE {
+            // Circle around, leaning away from the rest of the swarm.
+            to_enemy.perp().normalize_or_zero() + view.away_from_enemies() * 0.5
+        } else {
+            to_enemy.normalize_or_zero() * 0.5 + view.away_from_enemies() * 0.5
+        };
+
+        direction + view.towards_home()
+    }
+
+    fn shop(
+        &mut self,
+        player_stats: &PlayerStats,
+        difficulty: &Difficulty,
+    ) -> Option<&'static Upgrade> {
+        first_affordable(
+            &[
+                "Extra blanket",
+                "Melatonin",
+                "Milk and cookies",
+                "Fluffy pillow",
+            ],
+            player_stats,
+            difficulty,
+        )
+    }
+}
+
+/// Runs from everything and only grabs pickups when it's safe, buying sleep
+/// duration over anything else.
+#[derive(Default)]
+pub struct Coward;
+
+impl Coward {
+    const PANIC_RADIUS: f32 = 60.0;
+}
+
+impl BotStrategy for Coward {
+    fn name(&self) -> &'static str {
+        "coward"
+    }
+
+    fn steer(&mut self, view: &NightView) -> Vec2 {
+        let mut direction = view.away_from_enemies();
+
+        let safe = view
+            .enemies
+            .first()
+            .is_none_or(|enemy| enemy.distance(view.position) > Self::PANIC_RADIUS);
+        if let (true, Some(&pickup)) = (safe, view.pickups.first()) {
+            direction += (pickup - view.position).normalize_or_zero() * 0.5;
+        }
+
+        direction + view.towards_home()
+    }
+
+    fn shop(
+        &mut self,
+        player_stats: &PlayerStats,
+        difficulty: &Difficulty,
+    ) -> Option<&'static Upgrade> {
+        first_affordable(
+            &[
+                "Melatonin",
+                "Milk and cookies",
+                "Extra blanket",
+                "Fluffy pillow",
+            ],
+            player_stats,
+            difficulty,
+        )
+    }
+}
+
+/// Goes for every pickup it sees, and buys upgrades that make collecting easier.
+#[derive(Default)]
+pub struct Greedy;
+
+impl BotStrategy for Greedy {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn steer(&mut self, view: &NightView) -> Vec2 {
+        match view.pickups.first() {
+            Some(&pickup) => {
+                (pickup - view.position).normalize_or_zero() + view.away_from_enemies() * 0.3
+            }
+            None => view.away_from_enemies() + view.towards_home(),
+        }
+    }
+
+    fn shop(
+        &mut self,
+        player_stats: &PlayerStats,
+        difficulty: &Difficulty,
+    ) -> Option<&'static Upgrade> {
+        first_affordable(
+            &[
+                "Fluffy pillow",
+                "Chamomile tea",
+                "Melatonin",
+                "Milk and cookies",
+            ],
+            player_stats,
+            difficulty,
+        )
+    }
+}
+
+/// The names accepted by [`strategy_by_name`].
+pub const STRATEGIES: &[&str] = &["kiter", "coward", "greedy"];
+
+pub fn strategy_by_name(name: &str) -> Option<Box<dyn BotStrategy>> {
+    match name {
+        "kiter" => Some(Box::new(Kiter)),
+        "coward" => Some(Box::new(Coward)),
+        "greedy" => Some(Box::new(Greedy)),
+        _ => None,
+    }
+}
+
+/// Whether the autopilot is playing, and how.
+#[derive(Resource)]
+pub struct Autopilot {
+    pub enabled: bool,
+    pub strategy: Box<dyn BotStrategy>,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strategy: Box::new(Kiter),
+        }
+    }
+}
+
+impl Autopilot {
+    /// An autopilot that is playing with `strategy`.
+    pub fn new(strategy: Box<dyn BotStrategy>) -> Self {
+        Self {
+            enabled: true,
+            strategy,
+        }
+    }
+}
+
+fn autopilot_enabled(autopilot: Res<Autopilot>) -> bool {
+    autopilot.enabled
+}
+
+#[cfg(feature = "dev")]
+fn toggle_autopilot(keys: Res<ButtonInput<KeyCode>>, mut autopilot: ResMut<Autopilot>) {
+    if keys.just_pressed(KeyCode::F2) {
+        autopilot.enabled = !autopilot.enabled;
+        info!(
+            "Autopilot ({}) {}",
+            autopilot.strategy.name(),
+            if autopilot.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+fn go_shopping(
+    mut autopilot: ResMut<Autopilot>,
+    mut player_stats: ResMut<PlayerStats>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut upgrade_bought_writer: EventWriter<UpgradeBought>,
+) {
+    // Don't get in the way of winning or losing.
+    if !matches!(*next_state, NextState::Unchanged) {
+        return;
+    }
+
+    let shop_open = !campaign
+        .day(player_stats.day, &difficulty)
+        .has(DayRule::ShopClosed);
+    while let Some(upgrade) = autopilot
+        .strategy
+        .shop(&player_stats, &difficulty)
+        .filter(|_| shop_open)
+    {
+        if !purchase(&mut player_stats, upgrade, &difficulty) {
+            break;
+        }
+        upgrade_bought_writer.send(UpgradeBought {
+            name: upgrade.name,
+            cost: difficulty.price(upgrade.cost),
+        });
+    }
+
+    next_state.set(GameState::NightTime);
+}
+
+fn pilot(
+    mut autopilot: ResMut<Autopilot>,
+    mut movement_event_writer: EventWriter<MovementAction>,
+    player_query: Query<&Transform, With<NightPlayer>>,
+    pickup_query: Query<&Transform, With<Pickup>>,
+    enemy_index: Res<EnemyIndex>,
+    layout: Option<Res<LevelLayout>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let position = player_transform.translation.truncate();
+
+    let closest_first = |a: &Vec2, b: &Vec2| a.distance(position).total_cmp(&b.distance(position));
+    let mut enemies: Vec<_> = enemy_index
+        .within_radius(position, SIGHT_RADIUS)
+        .map(|(_, enemy)| enemy)
+        .collect();
+    enemies.sort_by(closest_first);
+    let mut pickups: Vec<_> = pickup_query
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .filter(|pickup| pickup.distance(position) < SIGHT_RADIUS)
+        .collect();
+    pickups.sort_by(closest_first);
+
+    let view = NightView {
+        position,
+        enemies,
+        pickups,
+        home: layout.map_or(position, |layout| layout.player_start),
+    };
+
+    let direction = autopilot.strategy.steer(&view);
+    if direction != Vec2::ZERO {
+        movement_event_writer.send(MovementAction::Move(direction.normalize()));
+    }
+}
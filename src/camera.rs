@@ -0,0 +1,115 @@
+use bevy::{prelude::*, transform::TransformSystem};
+use fastrand::Rng;
+
+use crate::{arena::Arena, night::Level, player::NightPlayer, GameState};
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenShake>();
+        app.add_systems(OnExit(GameState::NightTime), reset_camera);
+        app.add_systems(
+            PostUpdate,
+            (
+                follow_player.run_if(in_state(GameState::NightTime)),
+                apply_screen_shake,
+            )
+                .chain()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// How quickly the camera catches up with the player, higher is snappier.
+const FOLLOW_SMOOTHING: f32 = 5.0;
+/// The largest offset screen shake can move the camera by.
+const MAX_SHAKE_OFFSET: f32 = 20.0;
+/// How much trauma wears off per second.
+const TRAUMA_DECAY: f32 = 1.5;
+
+/// Marks the camera that follows the player during the night.
+#[derive(Component, Default)]
+pub struct CameraController {
+    /// Where the camera is looking, before screen shake is applied.
+    pub focus: Vec2,
+}
+
+/// Trauma based screen shake. Add trauma when something violent happens, the
+/// camera shakes with the square of the trauma, which wears off over time.
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    trauma: f32,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+}
+
+fn follow_player(
+    mut camera_query: Query<(&mut CameraController, &OrthographicProjection)>,
+    player_query: Query<&Transform, With<NightPlayer>>,
+    arenas: Res<Assets<Arena>>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let Ok((mut controller, projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let target = player_transform.translation.truncate();
+    let t = 1.0 - (-FOLLOW_SMOOTHING * time.delta_secs()).exp();
+    let mut focus = controller.focus.lerp(target, t);
+
+    // Don't show the void outside the room, unless the room is smaller than the view.
+    if let Some(arena) = arenas.get(&level.arena) {
+        let bounds = arena.bounds();
+        let half_view = projection.area.half_size();
+        for axis in 0..2 {
+            let min = bounds.min[axis] + half_view[axis];
+            let max = bounds.max[axis] - half_view[axis];
+            focus[axis] = if min <= max {
+                focus[axis].clamp(min, max)
+            } else {
+                bounds.center()[axis]
+            };
+        }
+    }
+
+    controller.focus = focus;
+}
+
+fn apply_screen_shake(
+    mut camera_query: Query<(&mut Transform, &CameraController)>,
+    mut shake: ResMut<ScreenShake>,
+    time: Res<Time>,
+) {
+    let Ok((mut transform, controller)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let mut rng = Rng::new();
+    let strength = shake.trauma * shake.trauma;
+    let offset = Vec2::new(rng.f32() * 2.0 - 1.0, rng.f32() * 2.0 - 1.0) * MAX_SHAKE_OFFSET;
+    transform.translation = (controller.focus + offset * strength).extend(transform.translation.z);
+
+    shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_secs()).max(0.0);
+}
+
+/// Snaps the camera back to the origin for the day menus.
+fn reset_camera(mut camera_query: Query<&mut CameraController>, mut shake: ResMut<ScreenShake>) {
+    for mut controller in camera_query.iter_mut() {
+        controller.focus = Vec2::ZERO;
+    }
+    shake.trauma = 0.0;
+}
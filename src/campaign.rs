@@ -0,0 +1,116 @@
+//! The campaign: how many days a run lasts, how long a night must be slept to win,
+//! and what each day says and changes.
+
+use bevy::prelude::*;
+
+use crate::difficulty::Difficulty;
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Campaign>();
+    }
+}
+
+/// Something that is different about a day and the night after it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DayRule {
+    /// The night ends with the boss, which must be survived or defeated before
+    /// the run can be won.
+    Boss,
+    /// Multiplies how fast nests spawn enemies.
+    SpawnRate(f32),
+    /// Nothing can be bought during the day.
+    ShopClosed,
+}
+
+#[derive(Clone, Debug)]
+pub struct CampaignDay {
+    /// Shown in the day menu when no story in `narrative.ron` fits the day, and
+    /// above the stories that ask for it.
+    pub text: String,
+    pub rules: Vec<DayRule>,
+}
+
+impl CampaignDay {
+    fn new(text: &str, rules: &[DayRule]) -> Self {
+        Self {
+            text: text.to_string(),
+            rules: rules.to_vec(),
+        }
+    }
+
+    pub fn has(&self, rule: DayRule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    pub fn spawn_rate(&self) -> f32 {
+        self.rules
+            .iter()
+            .map(|rule| match rule {
+                DayRule::SpawnRate(rate) => *rate,
+                _ => 1.0,
+            })
+            .product()
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Campaign {
+    /// Seconds a night must last to win the run.
+    pub target_sleep: f32,
+    /// One entry per day. The last one is always the final day, whether the
+    /// difficulty adds days or takes them away.
+    pub days: Vec<CampaignDay>,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Self {
+            target_sleep: 60.0,
+            days: vec![
+                CampaignDay::new("It's the first day, better get to sleep", &[]),
+                CampaignDay::new(
+                    "You slept a while, but not enough. Guess you should try again",
+                    &[],
+                ),
+                CampaignDay::new("That was refreshing, but you still feel tired", &[]),
+                CampaignDay::new(
+                    "The bed fills you with dread, are you prepared for the night?",
+                    &[],
+                ),
+                CampaignDay::new("Surely you can sleep a full night right?", &[]),
+                CampaignDay::new(
+                    "You can't really keep going, this is your final chance",
+                    &[DayRule::Boss],
+                ),
+            ],
+        }
+    }
+}
+
+impl Campaign {
+    /// The last day of a run, after which it's lost.
+    pub fn last_day(&self, difficulty: &Difficulty) -> u32 {
+        (self.days.len() as i32 + difficulty.extra_days).max(1) as u32
+    }
+
+    /// The campaign entry for `day`, counting from one. Extra days repeat the
+    /// one before the final day, and when there are fewer days, the ones just
+    /// before the final day are left out.
+    pub fn day(&self, day: u32, difficulty: &Difficulty) -> &CampaignDay {
+        let last = self.days.len() - 1;
+        let index = if day >= self.last_day(difficulty) {
+            last
+        } else {
+            (day.max(1) as usize - 1).min(last.saturating_sub(1))
+        };
+        &self.days[index]
+    }
+
+    /// Whether a run that has reached `day` has played a night with the boss.
+    pub fn boss_played(&self, day: u32, difficulty: &Difficulty) -> bool {
+        (1..day).any(|day| self.day(day, difficulty).has(DayRule::Boss))
+    }
+}
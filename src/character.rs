@@ -5,13 +5,32 @@ pub struct CharacterControllerPlugin;
 
 impl Plugin for CharacterControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<MovementAction>().add_systems(
-            Update,
-            (keyboard_input, movement, apply_movement_damping).chain(),
-        );
+        app.add_event::<MovementAction>()
+            .configure_sets(
+                FixedUpdate,
+                CharacterSet::Input.before(CharacterSet::Movement),
+            )
+            .add_systems(FixedUpdate, keyboard_input.in_set(CharacterSet::Input))
+            .add_systems(
+                FixedUpdate,
+                (movement, apply_movement_damping)
+                    .chain()
+                    .in_set(CharacterSet::Movement),
+            )
+            .add_systems(PostProcessCollisions, kinematic_controller_collisions);
     }
 }
 
+/// Movement runs on the fixed timestep, after everything that decides where
+/// characters want to go.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharacterSet {
+    /// Systems that send [`MovementAction`]s.
+    Input,
+    /// Systems that move characters based on the actions.
+    Movement,
+}
+
 /// An event sent for a movement input action.
 #[derive(Event)]
 pub enum MovementAction {
@@ -26,6 +45,10 @@ pub struct CharacterController;
 #[derive(Component)]
 pub struct MovementAcceleration(Scalar);
 
+/// Scales the acceleration of a character, for temporary slowdowns.
+#[derive(Component)]
+pub struct MovementMultiplier(pub Scalar);
+
 /// The damping factor used for slowing down movement.
 #[derive(Component)]
 pub struct MovementDampingFactor(Scalar);
@@ -109,7 +132,11 @@ fn keyboard_input(
 fn movement(
     time: Res<Time>,
     mut movement_event_reader: EventReader<MovementAction>,
-    mut controllers: Query<(&MovementAcceleration, &mut LinearVelocity)>,
+    mut controllers: Query<(
+        &MovementAcceleration,
+        Option<&MovementMultiplier>,
+        &mut LinearVelocity,
+    )>,
 ) {
     // Precision is adjusted so that the example works with
     // both the `f32` and `f64` features. Otherwise you don't need this.
@@ -118,14 +145,15 @@ fn movement(
     // let max_speed = 500.0;
 
     for event in movement_event_reader.read() {
-        for (movement_acceleration, mut linear_velocity) in &mut controllers {
+        for (movement_acceleration, multiplier, mut linear_velocity) in &mut controllers {
+            let acceleration = movement_acceleration.0 * multiplier.map_or(1.0, |m| m.0);
             match event {
                 MovementAction::Move(direction) => {
                     if direction.x != 0.0 {
-                        linear_velocity.x += direction.x * movement_acceleration.0 * delta_time;
+                        linear_velocity.x += direction.x * acceleration * delta_time;
                     }
                     if direction.y != 0.0 {
-                        linear_velocity.y += direction.y * movement_acceleration.0 * delta_time;
+                        linear_velocity.y += direction.y * acceleration * delta_time;
                     }
                     //  if linear_velocity.length() > max_speed {
                     //      linear_velocity.0 = linear_velocity.normalize() * max_speed;
@@ -143,3 +171,56 @@ fn apply_movement_damping(mut query: Query<(&MovementDampingFactor, &mut LinearV
         linear_velocity.0 *= damping_factor.0;
     }
 }
+
+/// Pushes character controllers out of static bodies such as walls.
+///
+/// Character controllers are kinematic, so the physics solver doesn't
+/// resolve their collisions for us.
+fn kinematic_controller_collisions(
+    collisions: Res<Collisions>,
+    bodies: Query<&RigidBody>,
+    mut character_controllers: Query<
+        (&mut Position, &Rotation, &mut LinearVelocity),
+        With<CharacterController>,
+    >,
+) {
+    for contacts in collisions.iter() {
+        let (character, other, is_first) = if character_controllers.contains(contacts.entity1) {
+            (contacts.entity1, contacts.entity2, true)
+        } else if character_controllers.contains(contacts.entity2) {
+            (contacts.entity2, contacts.entity1, false)
+        } else {
+            continue;
+        };
+
+        if !bodies.get(other).is_ok_and(RigidBody::is_static) {
+            continue;
+        }
+
+        let Ok((mut position, rotation, mut linear_velocity)) =
+            character_controllers.get_mut(character)
+        else {
+            continue;
+        };
+
+        for manifold in contacts.manifolds.iter() {
+            let normal = if is_first {
+                -manifold.global_normal1(rotation)
+            } else {
+                -manifold.global_normal2(rotation)
+            };
+
+            for contact in manifold.contacts.iter() {
+                if contact.penetration > 0.0 {
+                    position.0 += normal * contact.penetration;
+                }
+            }
+
+            // Stop moving into the wall, but keep sliding along it.
+            let projection = linear_velocity.dot(normal);
+            if projection < 0.0 {
+                linear_velocity.0 -= normal * projection;
+            }
+        }
+    }
+}
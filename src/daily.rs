@@ -0,0 +1,232 @@
+//! The Daily Dream, a run that is the same for everyone on a given day. Its seed
+//! and mutators are worked out from the date alone, so it works offline, and it
+//! can be played once per day for a place on its own leaderboard.
+//!
+//! The seed and mutators must never change for a date once it has been played,
+//! so they use their own generator instead of `fastrand`, whose output may change
+//! between versions. Only they are stable, though: the night itself is played out
+//! with [`GameRng`](crate::night::GameRng), a `fastrand` generator, and with the
+//! rules of the build being played. Two builds can give the same date different
+//! rooms and waves, which is why every score records the build it was set on.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    day::{button_hover_effect_out, button_hover_effect_over, new_day},
+    player::PlayerStats,
+    scores::{today, HighScores},
+    GameState,
+};
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunMode>();
+        app.init_resource::<Mutators>();
+        app.add_systems(OnEnter(GameState::DayTime), apply_mutators.before(new_day));
+    }
+}
+
+/// Changes to the rules for a Daily Dream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mutator {
+    DoubleSpawns,
+    NoHealing,
+    FastEnemies,
+    Fragile,
+    Windfall,
+}
+
+impl Mutator {
+    /// Every mutator, in the order the daily pick is made from. Only ever add to
+    /// the end, or past dates get different mutators.
+    pub const ALL: &'static [Mutator] = &[
+        Mutator::DoubleSpawns,
+        Mutator::NoHealing,
+        Mutator::FastEnemies,
+        Mutator::Fragile,
+        Mutator::Windfall,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mutator::DoubleSpawns => "Double spawns",
+            Mutator::NoHealing => "No healing",
+            Mutator::FastEnemies => "Fast enemies",
+            Mutator::Fragile => "Fragile",
+            Mutator::Windfall => "Windfall",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Mutator::DoubleSpawns => "Nests spawn twice as often",
+            Mutator::NoHealing => "Comfort pickups do nothing",
+            Mutator::FastEnemies => "Enemies move 25% faster",
+            Mutator::Fragile => "Start with half the comfort",
+            Mutator::Windfall => "Start with 200 more rest",
+        }
+    }
+}
+
+/// Number of mutators in a Daily Dream.
+pub const DAILY_MUTATORS: usize = 2;
+
+/// Whether the current run is a normal one or a Daily Dream.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RunMode {
+    #[default]
+    Normal,
+    /// The Daily Dream for the date, as `YYYY-MM-DD`.
+    Daily(String),
+}
+
+/// The mutators in play for the current run.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Mutators(pub Vec<Mutator>);
+
+impl Mutators {
+    pub fn has(&self, mutator: Mutator) -> bool {
+        self.0.contains(&mutator)
+    }
+
+    pub fn spawn_rate(&self) -> f32 {
+        if self.has(Mutator::DoubleSpawns) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn enemy_speed(&self) -> f32 {
+        if self.has(Mutator::FastEnemies) {
+            1.25
+        } else {
+            1.0
+        }
+    }
+
+    pub fn healing(&self) -> bool {
+        !self.has(Mutator::NoHealing)
+    }
+
+    /// Changes to the stats a run starts with.
+    pub fn apply_to_start(&self, player_stats: &mut PlayerStats) {
+        if self.has(Mutator::Fragile) {
+            player_stats.comfort /= 2.0;
+        }
+        if self.has(Mutator::Windfall) {
+            player_stats.rest += 200;
+        }
+    }
+}
+
+/// One step of SplitMix64, which is small enough to keep stable forever.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The run seed for the Daily Dream on a date.
+pub fn daily_seed(year: u64, month: u64, day: u64) -> u64 {
+    let mut state = year * 10_000 + month * 100 + day;
+    splitmix64(&mut state)
+}
+
+/// The mutators for the Daily Dream with `seed`.
+pub fn daily_mutators(seed: u64) -> Vec<Mutator> {
+    let mut state = seed;
+    let mut pool = Mutator::ALL.to_vec();
+    (0..DAILY_MUTATORS)
+        .map(|_| pool.remove((splitmix64(&mut state) % pool.len() as u64) as usize))
+        .collect()
+}
+
+/// Today's Daily Dream: its date, seed and mutators.
+pub fn todays_dream() -> (String, u64, Vec<Mutator>) {
+    let date = today();
+    let seed = date_seed(&date);
+    (date, seed, daily_mutators(seed))
+}
+
+/// The seed for a `YYYY-MM-DD` date.
+fn date_seed(date: &str) -> u64 {
+    let mut parts = date.split('-').map(|part| part.parse().unwrap_or(0));
+    let mut next = || parts.next().unwrap_or(0);
+    daily_seed(next(), next(), next())
+}
+
+/// Whether today's Daily Dream can still be played.
+pub fn daily_available(high_scores: &HighScores) -> bool {
+    high_scores.last_daily.as_deref() != Some(today().as_str())
+}
+
+fn apply_mutators(mutators: Res<Mutators>, mut player_stats: ResMut<PlayerStats>) {
+    // Only at the start of a run, before the first day.
+    if player_stats.day == 0 {
+        mutators.apply_to_start(&mut player_stats);
+    }
+}
+
+/// The button for today's Daily Dream on the end screens and in the journal, or a
+/// note that it has been played already.
+pub(crate) fn spawn_daily_link(menu: &mut ChildBuilder, high_scores: &HighScores) {
+    let node = Node {
+        margin: UiRect::all(Val::Px(10.0)),
+        ..default()
+    };
+    let font = TextFont {
+        font_size: 12.0,
+        ..default()
+    };
+
+    if !daily_available(high_scores) {
+        menu.spawn((
+            node,
+            Text::new("You've had today's Daily Dream, come back tomorrow"),
+            font,
+        ));
+        return;
+    }
+
+    let (_, _, mutators) = todays_dream();
+    let names: Vec<_> = mutators.iter().map(|mutator| mutator.name()).collect();
+    menu.spawn((
+        node,
+        Text::new(format!("Today's Daily Dream: {}", names.join(", "))),
+        font,
+        BackgroundColor(Color::NONE),
+    ))
+    .observe(button_hover_effect_over)
+    .observe(button_hover_effect_out)
+    .observe(start_daily);
+}
+
+/// Starts a run of today's Daily Dream, using up the day's attempt.
+fn start_daily(
+    trigger: Trigger<Pointer<Click>>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut run_mode: ResMut<RunMode>,
+    mut mutators: ResMut<Mutators>,
+    mut high_scores: ResMut<HighScores>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if trigger.button != PointerButton::Primary || !daily_available(&high_scores) {
+        return;
+    }
+
+    let (date, seed, daily) = todays_dream();
+    info!("Starting the Daily Dream for {date}");
+    high_scores.last_daily = Some(date.clone());
+    high_scores.save();
+
+    *player_stats = PlayerStats { seed, ..default() };
+    *run_mode = RunMode::Daily(date);
+    *mutators = Mutators(daily);
+    next_state.set(GameState::DayTime);
+}
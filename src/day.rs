@@ -1,14 +1,32 @@
 use bevy::{color::palettes::tailwind, prelude::*};
 
-use crate::{player::PlayerStats, GameState};
+use crate::{
+    campaign::{Campaign, DayRule},
+    daily::{spawn_daily_link, Mutators, RunMode},
+    difficulty::Difficulty,
+    journal::{award_dreams, DreamJournal},
+    narrative::{spawn_vignette, TodaysVignette},
+    player::PlayerStats,
+    replay::Replay,
+    scores::{record_score, spawn_high_scores, HighScores, LastScore},
+    stats::{spawn_run_summary, RunStats},
+    GameState,
+};
 
 pub struct DayPlugin;
 
 impl Plugin for DayPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<UpgradeBought>();
         app.add_systems(OnEnter(GameState::DayTime), (new_day, spawn_menus).chain());
-        app.add_systems(OnEnter(GameState::GameOver), spawn_over);
-        app.add_systems(OnEnter(GameState::GameWon), spawn_won);
+        app.add_systems(
+            OnEnter(GameState::GameOver),
+            spawn_over.after(award_dreams).after(record_score),
+        );
+        app.add_systems(
+            OnEnter(GameState::GameWon),
+            spawn_won.after(award_dreams).after(record_score),
+        );
         app.add_systems(Update, update_stats);
     }
 }
@@ -47,6 +65,31 @@ pub const UPGRADES: &[Upgrade] = &[
             player_stats.comfort += 5.0;
         },
     },
+    Upgrade {
+        name: "Fluffy pillow",
+        description: &["+15 pickup radius"],
+        cost: 60,
+        effect: |player_stats| {
+            player_stats.pickup_radius += 15.0;
+        },
+    },
+    Upgrade {
+        name: "Chamomile tea",
+        description: &["+0.25 sleep intensity"],
+        cost: 70,
+        effect: |player_stats| {
+            player_stats.sleep_intensity += 0.25;
+        },
+    },
+    Upgrade {
+        name: "Night light",
+        description: &["-0.25 sleep intensity", "+5 sleep duration"],
+        cost: 60,
+        effect: |player_stats| {
+            player_stats.sleep_intensity = (player_stats.sleep_intensity - 0.25).max(0.25);
+            player_stats.sleep_duration += 5.0;
+        },
+    },
     Upgrade {
         name: "Booze",
         description: &["-5 hydration", "-2 comfort", "+10 sleep duration"],
@@ -59,10 +102,74 @@ pub const UPGRADES: &[Upgrade] = &[
     },
 ];
 
+/// Upgrades that are only in the shop once they've been unlocked in the dream journal.
+pub const DREAM_UPGRADES: &[Upgrade] = &[
+    Upgrade {
+        name: "Weighted blanket",
+        description: &["+1 warmth", "+2 comfort"],
+        cost: 180,
+        effect: |player_stats| {
+            player_stats.warmth += 1.0;
+            player_stats.comfort += 2.0;
+        },
+    },
+    Upgrade {
+        name: "White noise machine",
+        description: &["+8 sleep duration"],
+        cost: 110,
+        effect: |player_stats| {
+            player_stats.sleep_duration += 8.0;
+        },
+    },
+    Upgrade {
+        name: "Lavender spray",
+        description: &["+25 pickup radius", "+0.25 sleep intensity"],
+        cost: 90,
+        effect: |player_stats| {
+            player_stats.pickup_radius += 25.0;
+            player_stats.sleep_intensity += 0.25;
+        },
+    },
+];
+
+/// Looks up an upgrade by name, including the ones unlocked in the dream journal.
+pub fn find_upgrade(name: &str) -> Option<&'static Upgrade> {
+    UPGRADES
+        .iter()
+        .chain(DREAM_UPGRADES)
+        .find(|upgrade| upgrade.name == name)
+}
+
+/// Sent whenever an upgrade is bought in the shop.
+#[derive(Event)]
+pub struct UpgradeBought {
+    pub name: &'static str,
+    pub cost: u32,
+}
+
 #[derive(Component)]
 pub struct StatsField;
 
-fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
+pub(crate) fn spawn_menus(
+    mut commands: Commands,
+    player_stats: Res<PlayerStats>,
+    journal: Res<DreamJournal>,
+    run_mode: Res<RunMode>,
+    mutators: Res<Mutators>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    todays_vignette: Res<TodaysVignette>,
+) {
+    let today = campaign.day(player_stats.day, &difficulty);
+    let last_day = campaign.last_day(&difficulty);
+    let shop_closed = today.has(DayRule::ShopClosed);
+    // Daily Dreams are the same for everyone, so the journal's upgrades stay out of the shop.
+    let shop: Vec<&Upgrade> = match *run_mode {
+        _ if shop_closed => Vec::new(),
+        RunMode::Normal => journal.shop_upgrades().collect(),
+        RunMode::Daily(_) => UPGRADES.iter().collect(),
+    };
+
     let mut menu = commands.spawn((
         StateScoped(GameState::DayTime),
         Node {
@@ -93,15 +200,23 @@ fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
                 ..default()
             },
         ));
-        let mut day = menu.spawn((
+        let color = if player_stats.day == last_day {
+            tailwind::RED_500.into()
+        } else {
+            Color::WHITE
+        };
+        spawn_vignette(menu, &todays_vignette, player_stats.day, color);
+
+        menu.spawn((
             Node {
                 margin: UiRect::all(Val::Px(10.0)),
                 ..default()
             },
             Text::new(format!(
-                "{} (day {})",
-                day_string(&player_stats),
-                player_stats.day
+                "Sleep {} seconds before day {} to win ({} difficulty)",
+                campaign.target_sleep,
+                last_day + 1,
+                difficulty.name()
             )),
             TextFont {
                 font_size: 12.0,
@@ -109,21 +224,40 @@ fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
             },
         ));
 
-        if player_stats.day == 6 {
-            day.insert(TextColor(tailwind::RED_500.into()));
+        if shop_closed {
+            menu.spawn((
+                Node {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                Text::new("The shop is closed today"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(tailwind::YELLOW_200.into()),
+            ));
         }
 
-        menu.spawn((
-            Node {
-                margin: UiRect::all(Val::Px(10.0)),
-                ..default()
-            },
-            Text::new("Sleep 60 seconds before day 7 to win"),
-            TextFont {
-                font_size: 12.0,
-                ..default()
-            },
-        ));
+        if let RunMode::Daily(date) = &*run_mode {
+            let rules: Vec<_> = mutators
+                .0
+                .iter()
+                .map(|mutator| format!("{}: {}", mutator.name(), mutator.description()))
+                .collect();
+            menu.spawn((
+                Node {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                Text::new(format!("Daily Dream for {date}. {}", rules.join(". "))),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(tailwind::YELLOW_200.into()),
+            ));
+        }
 
         menu.spawn((
             Node {
@@ -151,6 +285,8 @@ fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
                 "Warmth",
                 "Hydration",
                 "Sleep duration",
+                "Sleep intensity",
+                "Pickup radius",
                 "Rest gained",
                 "Rest",
             ] {
@@ -197,7 +333,7 @@ fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
                 description,
                 cost,
                 ..
-            } in UPGRADES.iter()
+            } in shop
             {
                 let mut upgrade = upgrades.spawn((
                     Node {
@@ -215,7 +351,7 @@ fn spawn_menus(mut commands: Commands, player_stats: Res<PlayerStats>) {
                             margin: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
                             ..default()
                         },
-                        Text::new(format!("{name} ({cost} rest)")),
+                        Text::new(format!("{name} ({} rest)", difficulty.price(*cost))),
                         TextFont {
                             font_size: 12.0,
                             ..default()
@@ -300,6 +436,8 @@ fn create_stat_string(player_stats: &PlayerStats, name: &str) -> String {
         "Hydration" => player_stats.hydration,
         "Rest" => player_stats.rest as f32,
         "Sleep duration" => player_stats.sleep_duration,
+        "Sleep intensity" => player_stats.sleep_intensity,
+        "Pickup radius" => player_stats.pickup_radius,
         "Rest gained" => player_stats.unsafe_rest as f32,
         _ => panic!("Unknown stat name: {name}"),
     };
@@ -307,20 +445,13 @@ fn create_stat_string(player_stats: &PlayerStats, name: &str) -> String {
     format!("{name:25} {stat}")
 }
 
-fn day_string(player_stats: &PlayerStats) -> &'static str {
-    match player_stats.day {
-        1 => "It's the first day, better get to sleep",
-        2 => "You slept a while, but not enough. Guess you should try again",
-        3 => "That was refreshing, but you still feel tired",
-        4 => "The bed fills you with dread, are you prepared for the night?",
-        5 => "Surely you can sleep a full night right?",
-        6 => "You can't really keep going, this is your final chance",
-        _ => "Day ???",
-    }
-}
-
-fn start_level(trigger: Trigger<Pointer<Click>>, mut next_state: ResMut<NextState<GameState>>) {
-    if trigger.button == PointerButton::Primary {
+fn start_level(
+    trigger: Trigger<Pointer<Click>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    replay: Option<Res<Replay>>,
+) {
+    // The replay decides when nights start.
+    if trigger.button == PointerButton::Primary && replay.is_none() {
         next_state.set(GameState::NightTime);
     }
 }
@@ -334,13 +465,17 @@ fn quit(trigger: Trigger<Pointer<Click>>, mut app_exit: EventWriter<AppExit>) {
 fn buy_upgrade(
     trigger: Trigger<Pointer<Click>>,
     mut player_stats: ResMut<PlayerStats>,
+    difficulty: Res<Difficulty>,
     mut upgrades: Query<(&Upgrade, &mut BackgroundColor)>,
+    mut upgrade_bought_writer: EventWriter<UpgradeBought>,
 ) {
     if trigger.button == PointerButton::Primary {
         if let Ok((upgrade, mut bg_color)) = upgrades.get_mut(trigger.entity()) {
-            if player_stats.rest >= upgrade.cost {
-                (upgrade.effect)(&mut player_stats);
-                player_stats.rest -= upgrade.cost;
+            if purchase(&mut player_stats, upgrade, &difficulty) {
+                upgrade_bought_writer.send(UpgradeBought {
+                    name: upgrade.name,
+                    cost: difficulty.price(upgrade.cost),
+                });
             } else {
                 bg_color.0 = tailwind::RED_500.into();
             }
@@ -348,7 +483,23 @@ fn buy_upgrade(
     }
 }
 
-fn button_hover_effect_over(
+/// Buys the upgrade if the player has enough rest, returning whether it was bought.
+pub fn purchase(
+    player_stats: &mut PlayerStats,
+    upgrade: &Upgrade,
+    difficulty: &Difficulty,
+) -> bool {
+    let cost = difficulty.price(upgrade.cost);
+    if player_stats.rest < cost {
+        return false;
+    }
+
+    (upgrade.effect)(player_stats);
+    player_stats.rest -= cost;
+    true
+}
+
+pub(crate) fn button_hover_effect_over(
     trigger: Trigger<Pointer<Over>>,
     mut node_query: Query<(&mut Node, &mut BackgroundColor)>,
 ) {
@@ -359,7 +510,7 @@ fn button_hover_effect_over(
     bg_color.0 = tailwind::BLUE_500.into();
 }
 
-fn button_hover_effect_out(
+pub(crate) fn button_hover_effect_out(
     trigger: Trigger<Pointer<Out>>,
     mut node_query: Query<(&mut Node, &mut BackgroundColor)>,
 ) {
@@ -370,18 +521,34 @@ fn button_hover_effect_out(
     bg_color.0 = Color::NONE;
 }
 
-fn new_day(mut player_stats: ResMut<PlayerStats>, mut next_state: ResMut<NextState<GameState>>) {
+pub(crate) fn new_day(
+    mut player_stats: ResMut<PlayerStats>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
     player_stats.rest += player_stats.unsafe_rest;
     player_stats.day += 1;
 
-    if player_stats.sleep_duration >= 59.0 && !player_stats.died {
+    // Once the boss has come, it must be survived or defeated.
+    let boss_required = campaign.boss_played(player_stats.day, &difficulty);
+    if player_stats.sleep_duration >= campaign.target_sleep
+        && !player_stats.died
+        && (!boss_required || player_stats.boss_cleared)
+    {
         next_state.set(GameState::GameWon);
-    } else if player_stats.day > 6 {
+    } else if player_stats.day > campaign.last_day(&difficulty) {
         next_state.set(GameState::GameOver);
     }
 }
 
-fn spawn_over(mut commands: Commands) {
+fn spawn_over(
+    mut commands: Commands,
+    run_stats: Res<RunStats>,
+    journal: Res<DreamJournal>,
+    high_scores: Res<HighScores>,
+    last_score: Res<LastScore>,
+) {
     let mut menu = commands.spawn((
         StateScoped(GameState::GameOver),
         Node {
@@ -412,22 +579,33 @@ fn spawn_over(mut commands: Commands) {
                 ..default()
             },
         ));
-        menu.spawn((
-            Node {
-                margin: UiRect::all(Val::Px(10.0)),
-                ..default()
-            },
-            Text::new("Try again?"),
-            TextFont {
-                font_size: 12.0,
-                ..default()
-            },
-        ))
-        .observe(new_game);
+        if let Some(cause) = run_stats.nights.last().and_then(|night| night.woke_up) {
+            menu.spawn((
+                Node {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                Text::new(cause.description()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+        }
+        spawn_run_summary(menu, &run_stats);
+        spawn_high_scores(menu, &high_scores, &last_score);
+        spawn_journal_links(menu, &journal);
+        spawn_daily_link(menu, &high_scores);
     });
 }
 
-fn spawn_won(mut commands: Commands) {
+fn spawn_won(
+    mut commands: Commands,
+    run_stats: Res<RunStats>,
+    journal: Res<DreamJournal>,
+    high_scores: Res<HighScores>,
+    last_score: Res<LastScore>,
+) {
     let mut menu = commands.spawn((
         StateScoped(GameState::GameWon),
         Node {
@@ -458,28 +636,75 @@ fn spawn_won(mut commands: Commands) {
                 ..default()
             },
         ));
-        menu.spawn((
-            Node {
-                margin: UiRect::all(Val::Px(10.0)),
-                ..default()
-            },
-            Text::new("Try again?"),
-            TextFont {
-                font_size: 12.0,
-                ..default()
-            },
-        ))
-        .observe(new_game);
+        spawn_run_summary(menu, &run_stats);
+        spawn_high_scores(menu, &high_scores, &last_score);
+        spawn_journal_links(menu, &journal);
+        spawn_daily_link(menu, &high_scores);
     });
 }
 
-fn new_game(
+/// How many dreams the run added to the journal, and the ways out of the end screens.
+fn spawn_journal_links(menu: &mut ChildBuilder, journal: &DreamJournal) {
+    menu.spawn((
+        Node {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        Text::new(format!(
+            "You wrote {} dreams in your journal, it now holds {}",
+            journal.last_award, journal.dreams
+        )),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+    ));
+    menu.spawn((
+        Node {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        Text::new("Read the dream journal"),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+    ))
+    .observe(button_hover_effect_over)
+    .observe(button_hover_effect_out)
+    .observe(open_journal);
+    menu.spawn((
+        Node {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        Text::new("Try again?"),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+    ))
+    .observe(new_game);
+}
+
+fn open_journal(trigger: Trigger<Pointer<Click>>, mut next_state: ResMut<NextState<GameState>>) {
+    if trigger.button == PointerButton::Primary {
+        next_state.set(GameState::Journal);
+    }
+}
+
+pub(crate) fn new_game(
     trigger: Trigger<Pointer<Click>>,
     mut player_stats: ResMut<PlayerStats>,
+    mut run_mode: ResMut<RunMode>,
+    mut mutators: ResMut<Mutators>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     if trigger.button == PointerButton::Primary {
         *player_stats = PlayerStats::default();
+        *run_mode = RunMode::Normal;
+        *mutators = Mutators::default();
         next_state.set(GameState::DayTime);
     }
 }
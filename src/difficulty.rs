@@ -0,0 +1,423 @@
+//! Difficulty presets, which scale the enemies, the shop, the starting stats and
+//! the length of the campaign. The player picks one in the dream journal, and
+//! can fine tune it into a custom difficulty there.
+
+use bevy::{color::palettes::tailwind, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    daily::RunMode,
+    day::{button_hover_effect_out, button_hover_effect_over, new_day},
+    journal::DreamJournal,
+    player::PlayerStats,
+    replay::Replay,
+    GameState,
+};
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>();
+        app.add_systems(OnEnter(GameState::DayTime), start_run.before(new_day));
+        app.add_systems(OnEnter(GameState::NightTime), spawn_hud);
+        app.add_systems(Update, update_picker.run_if(in_state(GameState::Journal)));
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Preset {
+    Cozy,
+    #[default]
+    Normal,
+    Insomniac,
+    /// Any other settings, tuned by the player.
+    Custom,
+}
+
+impl Preset {
+    pub const ALL: &'static [Preset] = &[
+        Preset::Cozy,
+        Preset::Normal,
+        Preset::Insomniac,
+        Preset::Custom,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Cozy => "Cozy",
+            Preset::Normal => "Normal",
+            Preset::Insomniac => "Insomniac",
+            Preset::Custom => "Custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How hard the current run is. Settings missing from a saved journal are normal.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Difficulty {
+    pub preset: Preset,
+    /// Multiplies how fast enemies move.
+    pub enemy_speed: f32,
+    /// Multiplies the health of nests, shielded elites and the boss. Above one,
+    /// regular enemies take more than one shot too.
+    pub enemy_health: f32,
+    /// Multiplies how fast the nests in the wave schedule spawn enemies.
+    pub spawn_rate: f32,
+    /// Multiplies shop prices.
+    pub prices: f32,
+    /// Rest added to, or taken from, the start of a run.
+    pub extra_rest: i32,
+    /// Comfort added to, or taken from, the start of a run.
+    pub extra_comfort: f32,
+    /// Days added to, or taken from, the campaign.
+    #[serde(alias = "days")]
+    pub extra_days: i32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::preset(Preset::Normal)
+    }
+}
+
+impl Difficulty {
+    /// The settings for `preset`. Custom difficulties start out as normal.
+    pub fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Cozy => Self {
+                preset,
+                enemy_speed: 0.85,
+                enemy_health: 1.0,
+                spawn_rate: 0.75,
+                prices: 0.8,
+                extra_rest: 150,
+                extra_comfort: 2.0,
+                extra_days: 2,
+            },
+            Preset::Normal | Preset::Custom => Self {
+                preset,
+                enemy_speed: 1.0,
+                enemy_health: 1.0,
+                spawn_rate: 1.0,
+                prices: 1.0,
+                extra_rest: 0,
+                extra_comfort: 0.0,
+                extra_days: 0,
+            },
+            Preset::Insomniac => Self {
+                preset,
+                enemy_speed: 1.15,
+                enemy_health: 2.0,
+                spawn_rate: 1.3,
+                prices: 1.25,
+                extra_rest: -100,
+                extra_comfort: -1.0,
+                extra_days: -1,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.preset.name()
+    }
+
+    /// What an upgrade that normally costs `cost` sells for.
+    pub fn price(&self, cost: u32) -> u32 {
+        (cost as f32 * self.prices).round() as u32
+    }
+
+    /// Changes to the stats a run starts with.
+    pub fn apply_to_start(&self, player_stats: &mut PlayerStats) {
+        player_stats.rest = player_stats.rest.saturating_add_signed(self.extra_rest);
+        player_stats.comfort = (player_stats.comfort + self.extra_comfort).max(1.0);
+    }
+}
+
+fn start_run(
+    mut difficulty: ResMut<Difficulty>,
+    mut player_stats: ResMut<PlayerStats>,
+    journal: Res<DreamJournal>,
+    run_mode: Res<RunMode>,
+    replay: Option<Res<Replay>>,
+) {
+    // Only at the start of a run, before the first day. Replays bring their own.
+    if player_stats.day != 0 || replay.is_some() {
+        return;
+    }
+
+    *difficulty = match *run_mode {
+        RunMode::Normal => journal.difficulty.clone(),
+        // Daily Dreams are the same for everyone.
+        RunMode::Daily(_) => Difficulty::default(),
+    };
+    difficulty.apply_to_start(&mut player_stats);
+}
+
+fn spawn_hud(mut commands: Commands, difficulty: Res<Difficulty>) {
+    commands.spawn((
+        StateScoped(GameState::NightTime),
+        Text::new(difficulty.name()),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(5.0),
+            ..default()
+        },
+    ));
+}
+
+/// A setting that can be tuned for a custom difficulty.
+pub struct Setting {
+    pub name: &'static str,
+    pub step: f32,
+    pub min: f32,
+    pub max: f32,
+    pub get: fn(&Difficulty) -> f32,
+    pub set: fn(&mut Difficulty, f32),
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        name: "Enemy speed",
+        step: 0.05,
+        min: 0.5,
+        max: 2.0,
+        get: |difficulty| difficulty.enemy_speed,
+        set: |difficulty, value| difficulty.enemy_speed = value,
+    },
+    Setting {
+        name: "Enemy health",
+        step: 0.25,
+        min: 1.0,
+        max: 5.0,
+        get: |difficulty| difficulty.enemy_health,
+        set: |difficulty, value| difficulty.enemy_health = value,
+    },
+    Setting {
+        name: "Spawn rate",
+        step: 0.05,
+        min: 0.5,
+        max: 3.0,
+        get: |difficulty| difficulty.spawn_rate,
+        set: |difficulty, value| difficulty.spawn_rate = value,
+    },
+    Setting {
+        name: "Shop prices",
+        step: 0.05,
+        min: 0.5,
+        max: 2.0,
+        get: |difficulty| difficulty.prices,
+        set: |difficulty, value| difficulty.prices = value,
+    },
+    Setting {
+        name: "Extra rest",
+        step: 50.0,
+        min: -250.0,
+        max: 1000.0,
+        get: |difficulty| difficulty.extra_rest as f32,
+        set: |difficulty, value| difficulty.extra_rest = value as i32,
+    },
+    Setting {
+        name: "Extra comfort",
+        step: 1.0,
+        min: -4.0,
+        max: 10.0,
+        get: |difficulty| difficulty.extra_comfort,
+        set: |difficulty, value| difficulty.extra_comfort = value,
+    },
+    Setting {
+        name: "Extra days",
+        step: 1.0,
+        min: -4.0,
+        max: 8.0,
+        get: |difficulty| difficulty.extra_days as f32,
+        set: |difficulty, value| difficulty.extra_days = value as i32,
+    },
+];
+
+impl Setting {
+    /// Moves the setting `steps` steps, and makes the difficulty a custom one.
+    pub fn adjust(&self, difficulty: &mut Difficulty, steps: f32) {
+        let value = ((self.get)(difficulty) + self.step * steps).clamp(self.min, self.max);
+        // Round away the error from adding up steps.
+        (self.set)(difficulty, (value / self.step).round() * self.step);
+        difficulty.preset = Preset::Custom;
+    }
+
+    fn format(&self, difficulty: &Difficulty) -> String {
+        let value = (self.get)(difficulty);
+        if self.step >= 1.0 {
+            format!("{value:.0}")
+        } else {
+            format!("{value:.2}")
+        }
+    }
+}
+
+#[derive(Component)]
+struct PresetButton(Preset);
+
+#[derive(Component)]
+struct SettingValue(usize);
+
+#[derive(Component)]
+struct SettingButton {
+    setting: usize,
+    steps: f32,
+}
+
+/// Buttons for choosing the difficulty of the next run, for the journal screen.
+pub(crate) fn spawn_difficulty_picker(menu: &mut ChildBuilder, difficulty: &Difficulty) {
+    menu.spawn(Node {
+        margin: UiRect::all(Val::Px(10.0)),
+        column_gap: Val::Px(10.0),
+        ..default()
+    })
+    .with_children(|presets| {
+        presets.spawn((
+            Text::new("Difficulty:"),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+        ));
+        for &preset in Preset::ALL {
+            presets
+                .spawn((
+                    PresetButton(preset),
+                    Text::new(preset.name()),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(preset_color(preset, difficulty)),
+                    BackgroundColor(Color::NONE),
+                ))
+                .observe(button_hover_effect_over)
+                .observe(button_hover_effect_out)
+                .observe(pick_preset);
+        }
+    });
+
+    menu.spawn(Node {
+        display: Display::Grid,
+        grid_template_columns: vec![
+            GridTrack::px(120.0),
+            GridTrack::px(20.0),
+            GridTrack::px(50.0),
+            GridTrack::px(20.0),
+        ],
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        ..default()
+    })
+    .with_children(|grid| {
+        let font = TextFont {
+            font_size: 10.0,
+            ..default()
+        };
+        for (i, setting) in SETTINGS.iter().enumerate() {
+            grid.spawn((Text::new(setting.name), font.clone()));
+            grid.spawn((
+                SettingButton {
+                    setting: i,
+                    steps: -1.0,
+                },
+                Text::new("-"),
+                font.clone(),
+                BackgroundColor(Color::NONE),
+            ))
+            .observe(button_hover_effect_over)
+            .observe(button_hover_effect_out)
+            .observe(adjust_setting);
+            grid.spawn((
+                SettingValue(i),
+                Text::new(setting.format(difficulty)),
+                font.clone(),
+            ));
+            grid.spawn((
+                SettingButton {
+                    setting: i,
+                    steps: 1.0,
+                },
+                Text::new("+"),
+                font.clone(),
+                BackgroundColor(Color::NONE),
+            ))
+            .observe(button_hover_effect_over)
+            .observe(button_hover_effect_out)
+            .observe(adjust_setting);
+        }
+    });
+}
+
+fn preset_color(preset: Preset, difficulty: &Difficulty) -> Color {
+    if preset == difficulty.preset {
+        tailwind::YELLOW_200.into()
+    } else {
+        Color::WHITE
+    }
+}
+
+fn pick_preset(
+    trigger: Trigger<Pointer<Click>>,
+    mut journal: ResMut<DreamJournal>,
+    buttons: Query<&PresetButton>,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+
+    if let Ok(PresetButton(preset)) = buttons.get(trigger.entity()) {
+        if *preset == Preset::Custom {
+            journal.difficulty.preset = Preset::Custom;
+        } else {
+            journal.difficulty = Difficulty::preset(*preset);
+        }
+        journal.save();
+    }
+}
+
+fn adjust_setting(
+    trigger: Trigger<Pointer<Click>>,
+    mut journal: ResMut<DreamJournal>,
+    buttons: Query<&SettingButton>,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+
+    if let Ok(button) = buttons.get(trigger.entity()) {
+        SETTINGS[button.setting].adjust(&mut journal.difficulty, button.steps);
+        journal.save();
+    }
+}
+
+fn update_picker(
+    journal: Res<DreamJournal>,
+    mut presets: Query<(&PresetButton, &mut TextColor)>,
+    mut values: Query<(&SettingValue, &mut Text)>,
+) {
+    if !journal.is_changed() {
+        return;
+    }
+
+    for (PresetButton(preset), mut color) in presets.iter_mut() {
+        color.0 = preset_color(*preset, &journal.difficulty);
+    }
+    for (SettingValue(i), mut text) in values.iter_mut() {
+        text.0 = SETTINGS[*i].format(&journal.difficulty);
+    }
+}
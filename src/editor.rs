@@ -0,0 +1,295 @@
+//! A small level editor for the `dev` feature. Press F1 during the night to
+//! pause the game and edit the room:
+//!
+//! - Drag furniture and nests around with the left mouse button
+//! - `O` places a toy, `N` places a nest, `P` moves the player start
+//! - `Delete` removes whatever was last picked
+//! - `Ctrl+S` saves the room to `assets/levels/<name>.level.ron`
+
+use std::{fs, path::PathBuf};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    arena::Arena,
+    difficulty::Difficulty,
+    enemy::{EnemyRules, EnemySpawner, EnemyType},
+    game_assets::{GameAssets, SPAWNER_SIZE},
+    layout::{
+        spawn_furniture, FixedSpawner, Furniture, FurnitureKind, FurniturePiece, LevelFile,
+        LevelLayout, ObstacleDef, SpawnerDef,
+    },
+    night::{spawn_nest, Level, LevelName},
+    player::NightPlayer,
+    GameState,
+};
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Editor>();
+        app.add_systems(OnExit(GameState::NightTime), close_editor);
+        app.add_systems(
+            Update,
+            (
+                toggle_editor,
+                (pick, drag, place, delete, save_level)
+                    .chain()
+                    .run_if(editor_active.and(resource_exists::<LevelLayout>)),
+            )
+                .chain()
+                .run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// Name the level is saved under when playing a generated room.
+const DEFAULT_LEVEL_NAME: &str = "custom";
+const NEW_TOY_SIZE: Vec2 = Vec2::new(40.0, 40.0);
+const NEW_NEST_SPAWN_RATE: f32 = 0.5;
+
+#[derive(Resource, Default)]
+pub struct Editor {
+    pub active: bool,
+    selected: Option<Entity>,
+    dragging: bool,
+    /// Offset from the cursor to the center of the dragged entity.
+    grab_offset: Vec2,
+}
+
+fn editor_active(editor: Res<Editor>) -> bool {
+    editor.active
+}
+
+fn toggle_editor(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !keys.just_pressed(KeyCode::F1) {
+        return;
+    }
+
+    editor.active = !editor.active;
+    editor.selected = None;
+    editor.dragging = false;
+    if editor.active {
+        time.pause();
+        info!("Level editor opened");
+    } else {
+        time.unpause();
+        info!("Level editor closed");
+    }
+}
+
+fn close_editor(mut editor: ResMut<Editor>, mut time: ResMut<Time<Virtual>>) {
+    *editor = Editor::default();
+    time.unpause();
+}
+
+fn cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+fn pick(
+    mouse: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    furniture_query: Query<(Entity, &Transform), With<FurniturePiece>>,
+    spawner_query: Query<(Entity, &Transform), With<EnemySpawner>>,
+    mut editor: ResMut<Editor>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    // Nests are small and drawn on top, so they win over the furniture under them.
+    let nest = spawner_query.iter().find(|(_, transform)| {
+        Rect::from_center_size(transform.translation.truncate(), Vec2::splat(SPAWNER_SIZE))
+            .contains(cursor)
+    });
+    let furniture = || {
+        furniture_query
+            .iter()
+            .filter(|(_, transform)| {
+                Rect::from_center_size(transform.translation.truncate(), transform.scale.truncate())
+                    .contains(cursor)
+            })
+            // Toys on top of rugs.
+            .max_by(|(_, a), (_, b)| a.translation.z.total_cmp(&b.translation.z))
+    };
+
+    match nest.or_else(furniture) {
+        Some((entity, transform)) => {
+            editor.selected = Some(entity);
+            editor.dragging = true;
+            editor.grab_offset = transform.translation.truncate() - cursor;
+        }
+        None => {
+            editor.selected = None;
+            editor.dragging = false;
+        }
+    }
+}
+
+fn drag(
+    mouse: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut transform_query: Query<&mut Transform>,
+    mut editor: ResMut<Editor>,
+) {
+    if mouse.just_released(MouseButton::Left) {
+        editor.dragging = false;
+    }
+
+    let (true, Some(selected)) = (editor.dragging, editor.selected) else {
+        return;
+    };
+
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    if let Ok(mut transform) = transform_query.get_mut(selected) {
+        let position = cursor + editor.grab_offset;
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn place(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut player_query: Query<&mut Transform, With<NightPlayer>>,
+    mut layout: ResMut<LevelLayout>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+    difficulty: Res<Difficulty>,
+    game_assets: Res<GameAssets>,
+    mut editor: ResMut<Editor>,
+) {
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyO) {
+        let furniture = Furniture {
+            kind: FurnitureKind::Toy,
+            rect: Rect::from_center_size(cursor, NEW_TOY_SIZE),
+        };
+        editor.selected = Some(spawn_furniture(&mut commands, &furniture, &game_assets));
+    }
+
+    if keys.just_pressed(KeyCode::KeyN) {
+        let Some(rules) = rules.get(&level.rules) else {
+            return;
+        };
+        let spawner = EnemySpawner {
+            spawn_rate: NEW_NEST_SPAWN_RATE,
+            radius: 10.0,
+            spawn_type: EnemyType::Basic,
+        };
+        let nest = spawn_nest(
+            &mut commands,
+            &game_assets,
+            rules,
+            &difficulty,
+            cursor,
+            spawner,
+        );
+        commands.entity(nest).insert(FixedSpawner);
+        editor.selected = Some(nest);
+    }
+
+    if keys.just_pressed(KeyCode::KeyP) {
+        layout.player_start = cursor;
+        for mut transform in player_query.iter_mut() {
+            transform.translation = cursor.extend(transform.translation.z);
+        }
+    }
+}
+
+fn delete(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>, mut editor: ResMut<Editor>) {
+    if !keys.just_pressed(KeyCode::Delete) {
+        return;
+    }
+
+    if let Some(selected) = editor.selected.take() {
+        commands.entity(selected).despawn_recursive();
+        editor.dragging = false;
+    }
+}
+
+fn save_level(
+    keys: Res<ButtonInput<KeyCode>>,
+    furniture_query: Query<(&FurniturePiece, &Transform)>,
+    spawner_query: Query<(&EnemySpawner, &Transform), With<FixedSpawner>>,
+    layout: Res<LevelLayout>,
+    arenas: Res<Assets<Arena>>,
+    level: Res<Level>,
+    level_name: Res<LevelName>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !(ctrl && keys.just_pressed(KeyCode::KeyS)) {
+        return;
+    }
+
+    let Some(arena) = arenas.get(&level.arena) else {
+        return;
+    };
+
+    let file = LevelFile {
+        arena: arena.clone(),
+        player_start: layout.player_start.into(),
+        obstacles: furniture_query
+            .iter()
+            .map(|(piece, transform)| ObstacleDef {
+                kind: piece.0,
+                center: transform.translation.truncate().into(),
+                size: transform.scale.truncate().into(),
+            })
+            .collect(),
+        spawners: spawner_query
+            .iter()
+            .map(|(spawner, transform)| SpawnerDef {
+                position: transform.translation.truncate().into(),
+                spawn_rate: spawner.spawn_rate,
+                spawn_type: spawner.spawn_type,
+            })
+            .collect(),
+    };
+
+    let name = level_name.0.as_deref().unwrap_or(DEFAULT_LEVEL_NAME);
+    let dir = levels_dir();
+    let path = dir.join(format!("{name}.level.ron"));
+    let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+            fs::write(&path, contents).map_err(|err| err.to_string())
+        });
+
+    match result {
+        Ok(()) => info!("Saved level to {}", path.display()),
+        Err(err) => error!("Failed to save level to {}: {err}", path.display()),
+    }
+}
+
+/// Where levels are loaded from, however the game was started.
+fn levels_dir() -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join(AssetPlugin::DEFAULT_UNPROCESSED_FILE_PATH)
+        .join("levels")
+}
@@ -12,14 +12,22 @@ impl Plugin for EffectsPlugin {
 #[derive(Resource)]
 pub struct Effects {
     pub death_effect: ParticleEffect,
+    pub nest_death_effect: ParticleEffect,
+    pub spawn_marker_effect: ParticleEffect,
 }
 
 impl FromWorld for Effects {
     fn from_world(world: &mut World) -> Self {
         let mut effects = world.resource_mut::<Assets<EffectAsset>>();
         let death_effect = ParticleEffect::new(effects.add(death_effect()));
+        let nest_death_effect = ParticleEffect::new(effects.add(nest_death_effect()));
+        let spawn_marker_effect = ParticleEffect::new(effects.add(spawn_marker_effect()));
 
-        Self { death_effect }
+        Self {
+            death_effect,
+            nest_death_effect,
+            spawn_marker_effect,
+        }
     }
 }
 
@@ -67,3 +75,96 @@ fn death_effect() -> EffectAsset {
         .render(ColorOverLifetimeModifier::new(gradient))
         .render(round)
 }
+
+fn nest_death_effect() -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0.0, Vec4::new(4.0, 4.0, 4.0, 1.0));
+    gradient.add_key(0.5, Vec4::new(1.0, 0.3, 0.3, 0.8));
+    gradient.add_key(1.0, Vec4::new(0.5, 0.1, 0.1, 0.0));
+
+    let writer = ExprWriter::new();
+
+    let age = writer.lit(0.).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+
+    let lifetime = writer.lit(1.5).uniform(writer.lit(2.5)).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+    let init_pos = SetPositionCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        radius: writer.lit(5.0).expr(),
+        dimension: ShapeDimension::Volume,
+    };
+
+    let init_vel = SetVelocityCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        speed: writer.lit(60.0).uniform(writer.lit(160.0)).expr(),
+    };
+
+    let mut module = writer.finish();
+
+    let round = RoundModifier::constant(&mut module, 1.0);
+
+    let spawner = SpawnerSettings::once(200.0.into());
+    EffectAsset::new(4096, spawner, module)
+        .with_name("nest death effect")
+        .init(init_pos)
+        .init(init_vel)
+        .init(init_age)
+        .init(init_lifetime)
+        .render(SizeOverLifetimeModifier {
+            gradient: Gradient::constant(Vec3::splat(6.0)),
+            screen_space_size: false,
+        })
+        .render(ColorOverLifetimeModifier::new(gradient))
+        .render(round)
+}
+
+fn spawn_marker_effect() -> EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0.0, Vec4::new(3.0, 0.2, 0.2, 0.0));
+    gradient.add_key(0.3, Vec4::new(3.0, 0.2, 0.2, 0.8));
+    gradient.add_key(1.0, Vec4::new(3.0, 0.2, 0.2, 0.0));
+
+    let writer = ExprWriter::new();
+
+    let age = writer.lit(0.).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+
+    let lifetime = writer.lit(0.5).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+    let init_pos = SetPositionCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        radius: writer.lit(14.0).expr(),
+        dimension: ShapeDimension::Surface,
+    };
+
+    // Particles drift inwards, towards where the enemy will appear.
+    let init_vel = SetVelocityCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        speed: writer.lit(-20.0).expr(),
+    };
+
+    let mut module = writer.finish();
+
+    let round = RoundModifier::constant(&mut module, 2.0 / 3.0);
+
+    let spawner = SpawnerSettings::rate(30.0.into());
+    EffectAsset::new(1024, spawner, module)
+        .with_name("spawn marker effect")
+        .init(init_pos)
+        .init(init_vel)
+        .init(init_age)
+        .init(init_lifetime)
+        .render(SizeOverLifetimeModifier {
+            gradient: Gradient::constant(Vec3::splat(4.0)),
+            screen_space_size: false,
+        })
+        .render(ColorOverLifetimeModifier::new(gradient))
+        .render(round)
+}
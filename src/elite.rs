@@ -0,0 +1,192 @@
+use avian2d::prelude::{Collider, CollisionLayers, LinearVelocity, RigidBody};
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    boss::Boss,
+    difficulty::Difficulty,
+    enemy::{EnemyDiedEvent, EnemyHealth, EnemyRules, EnemyType},
+    game_assets::{GameAssets, MINION_RADIUS},
+    night::{GameRng, Level},
+    spatial::EnemyIndex,
+    GameLayer, GameState,
+};
+
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<EliteRules>::new(&["elites.ron"]));
+        app.add_systems(
+            FixedUpdate,
+            (regenerating_aura, split_elites).run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AffixKind {
+    /// Takes `strength` extra shots to kill.
+    Shielded,
+    /// Moves `strength` times faster.
+    Fast,
+    /// Splits into `strength` basic enemies when killed.
+    Splitting,
+    /// Heals nearby enemies by `strength` health per second.
+    Regenerating,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AffixRules {
+    pub kind: AffixKind,
+    pub weight: f32,
+    pub strength: f32,
+    pub rest_multiplier: f32,
+    pub color: (f32, f32, f32),
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize)]
+pub struct EliteRules {
+    pub base_chance: f32,
+    pub chance_per_day: f32,
+    pub nightmare_bonus: f32,
+    pub max_chance: f32,
+    pub aura_radius: f32,
+    pub affixes: Vec<AffixRules>,
+}
+
+impl EliteRules {
+    /// The chance that a newly spawned enemy is an elite.
+    pub fn chance(&self, day: u32, nightmare: bool) -> f32 {
+        let mut chance = self.base_chance + self.chance_per_day * day.saturating_sub(1) as f32;
+        if nightmare {
+            chance += self.nightmare_bonus;
+        }
+        chance.min(self.max_chance)
+    }
+
+    /// Rolls whether a new enemy becomes an elite, and with which affix.
+    pub fn roll(&self, rng: &mut Rng, day: u32, nightmare: bool) -> Option<&AffixRules> {
+        if rng.f32() >= self.chance(day, nightmare) {
+            return None;
+        }
+
+        let total: f32 = self.affixes.iter().map(|affix| affix.weight).sum();
+        let mut pick = rng.f32() * total;
+        for affix in &self.affixes {
+            if pick < affix.weight {
+                return Some(affix);
+            }
+            pick -= affix.weight;
+        }
+        self.affixes.last()
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Elite {
+    pub affix: AffixKind,
+    pub strength: f32,
+    pub rest_multiplier: f32,
+}
+
+impl Elite {
+    pub fn speed_multiplier(&self) -> f32 {
+        match self.affix {
+            AffixKind::Fast => self.strength,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Turns a freshly spawned enemy into an elite with the given affix.
+pub fn make_elite(
+    enemy: &mut EntityCommands,
+    affix: &AffixRules,
+    game_assets: &GameAssets,
+    difficulty: &Difficulty,
+) {
+    enemy.insert(Elite {
+        affix: affix.kind,
+        strength: affix.strength,
+        rest_multiplier: affix.rest_multiplier,
+    });
+    if affix.kind == AffixKind::Shielded {
+        enemy.insert(EnemyHealth::new(
+            (1.0 + affix.strength) * difficulty.enemy_health,
+        ));
+    }
+
+    if let Some(material) = game_assets.elite_materials.get(&affix.kind) {
+        enemy.with_child((
+            Transform::from_xyz(0.0, 0.0, -0.1),
+            Mesh2d(game_assets.elite_outline_mesh.clone()),
+            MeshMaterial2d(material.clone()),
+        ));
+    }
+}
+
+fn regenerating_aura(
+    auras: Query<(&Elite, &GlobalTransform)>,
+    mut enemies: Query<&mut EnemyHealth, Without<Boss>>,
+    enemy_index: Res<EnemyIndex>,
+    rules: Res<Assets<EliteRules>>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    let Some(rules) = rules.get(&level.elites) else {
+        return;
+    };
+
+    for (elite, aura_transform) in auras.iter() {
+        if elite.affix != AffixKind::Regenerating {
+            continue;
+        }
+        let position = aura_transform.translation().truncate();
+        for (entity, _) in enemy_index.within_radius(position, rules.aura_radius) {
+            if let Ok(mut health) = enemies.get_mut(entity) {
+                health.heal(elite.strength * time.delta_secs());
+            }
+        }
+    }
+}
+
+fn split_elites(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut enemy_died_event_reader: EventReader<EnemyDiedEvent>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    for event in enemy_died_event_reader.read() {
+        let Some(elite) = event.elite else {
+            continue;
+        };
+        if !event.killed || elite.affix != AffixKind::Splitting {
+            continue;
+        }
+
+        for _ in 0..elite.strength as u32 {
+            let direction = Vec2::from_angle(rng.f32() * std::f32::consts::TAU);
+            let pos = event.transform.translation().truncate() + direction * 10.0;
+            commands.spawn((
+                EnemyType::Basic,
+                StateScoped(GameState::NightTime),
+                Transform::from_translation(pos.extend(0.0)),
+                Mesh2d(game_assets.minion_mesh.clone()),
+                Collider::circle(MINION_RADIUS),
+                CollisionLayers::new(GameLayer::Enemy, [GameLayer::Default, GameLayer::Player]),
+                RigidBody::Dynamic,
+                MeshMaterial2d(game_assets.enemy_material.clone()),
+                LinearVelocity(direction * rules.base_speed),
+            ));
+        }
+    }
+}
@@ -1,13 +1,23 @@
-use avian2d::prelude::{Collider, Collision, CollisionLayers, LinearVelocity, RigidBody};
+use avian2d::prelude::{Collider, Collision, CollisionLayers, LinearVelocity, RigidBody, Sensor};
 use bevy::{math::vec2, prelude::*};
 use bevy_common_assets::ron::RonAssetPlugin;
-use fastrand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    arena::Arena,
+    boss::Boss,
+    camera::ScreenShake,
+    campaign::Campaign,
+    daily::Mutators,
+    difficulty::Difficulty,
     effects::Effects,
-    night::Level,
-    player::{NightPlayer, PlayerShot, PlayerStats},
+    elite::{make_elite, Elite, EliteRules},
+    game_assets::{GameAssets, ENEMY_PROJECTILE_RADIUS, ENEMY_RADIUS},
+    layout::{FixedSpawner, LevelLayout},
+    night::{GameRng, Level, LevelState},
+    pickup::{spawn_pickup, Pickup},
+    player::{NightPlayer, PlayerDamaged, PlayerShot, PlayerStats},
+    sleep::SleepPhase,
     timed_entity::Timed,
     GameLayer, GameState,
 };
@@ -19,11 +29,18 @@ impl Plugin for EnemyPlugin {
         app.add_plugins(RonAssetPlugin::<EnemyRules>::new(&["enemies.ron"]));
         app.add_event::<EnemyDiedEvent>();
         app.add_systems(
-            Update,
+            FixedUpdate,
             (
-                spawn_enemies,
+                (spawn_enemies, hatch_telegraphs).chain(),
                 target_enemies,
-                (handle_collisions, handle_enemy_death).chain(),
+                ranged_enemies,
+                drift_spawners,
+                (
+                    handle_collisions,
+                    handle_enemy_projectiles,
+                    handle_enemy_death,
+                )
+                    .chain(),
             )
                 .run_if(in_state(GameState::NightTime)),
         );
@@ -34,15 +51,19 @@ impl Plugin for EnemyPlugin {
 pub struct EnemyDiedEvent {
     pub entity: Entity,
     pub transform: GlobalTransform,
+    pub enemy_type: EnemyType,
+    pub elite: Option<Elite>,
     pub killed: bool,
 }
 
-#[derive(Component, Default, Clone, Copy)]
+#[derive(Component, Default, Clone, Copy, Serialize, Deserialize)]
 #[require(Enemy)]
 pub enum EnemyType {
     #[default]
     Basic,
+    Ranged,
     Spawner,
+    Boss,
 }
 
 #[derive(Component, Default, Clone)]
@@ -56,24 +77,118 @@ pub struct EnemySpawner {
 #[derive(Component, Default, Deref, DerefMut)]
 pub struct LastSpawnTime(f32);
 
+/// A marker shown where an enemy is about to appear.
+#[derive(Component)]
+pub struct SpawnTelegraph {
+    pub spawn_type: EnemyType,
+    pub spawn_at: f32,
+}
+
 #[derive(Component, Default, Clone, Copy)]
 pub struct Enemy;
 
+/// Hit points for enemies that survive more than a single shot.
+#[derive(Component, Default, Clone, Copy, Deref, DerefMut)]
+pub struct EnemyHealth {
+    #[deref]
+    pub current: f32,
+    /// Healing never goes above this.
+    pub max: f32,
+}
+
+impl EnemyHealth {
+    pub fn new(health: f32) -> Self {
+        Self {
+            current: health,
+            max: health,
+        }
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+}
+
+/// An enemy that keeps its distance from the player and fires projectiles.
+#[derive(Component, Default)]
+pub struct RangedEnemy {
+    pub last_shot: f32,
+    pub strafe_direction: f32,
+}
+
+/// A projectile fired by a [`RangedEnemy`], only collides with the player.
+#[derive(Component)]
+pub struct EnemyProjectile {
+    pub damage: f32,
+}
+
 #[derive(Asset, TypePath, Serialize, Deserialize)]
 pub struct EnemyRules {
     pub base_speed: f32,
     pub health: f32,
+    /// Seconds the spawn marker is shown before the enemy appears.
+    pub spawn_delay: f32,
+    /// Enemies never spawn closer than this to the player.
+    pub min_spawn_distance: f32,
+    pub ranged: RangedRules,
+    pub spawner: SpawnerRules,
+    pub boss: BossRules,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BossRules {
+    pub health: f32,
+    pub radius: f32,
+    /// How many seconds before the end of the night the boss arrives.
+    pub duration: f32,
+    pub speed: f32,
+    pub charge_speed: f32,
+    pub charge_duration: f32,
+    pub attack_interval: f32,
+    pub minions: u32,
+    pub ring_projectiles: u32,
+    pub projectile_speed: f32,
+    pub projectile_damage: f32,
+    pub contact_damage: f32,
+    /// Health fractions at which the boss enters its next phase.
+    pub phase_thresholds: Vec<f32>,
+    pub rest_bonus: u32,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct SpawnerRules {
+    pub health: f32,
+    pub drift_speed: f32,
+    pub rest_bonus: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RangedRules {
+    pub speed: f32,
+    pub preferred_distance: f32,
+    pub projectile_speed: f32,
+    pub fire_rate: f32,
+    pub damage: f32,
+}
+
+/// How many directions around the player a spawn that's too close is pushed
+/// towards, starting with the far side of the nest.
+const PUSH_DIRECTIONS: usize = 8;
+
 fn spawn_enemies(
     mut commands: Commands,
     time: Res<Time>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut spawner_query: Query<(&EnemySpawner, &mut LastSpawnTime, &Transform)>,
     rules: Res<Assets<EnemyRules>>,
     level: Res<Level>,
+    effects: Res<Effects>,
+    player_stats: Res<PlayerStats>,
+    mutators: Res<Mutators>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    layout: Option<Res<LevelLayout>>,
     player_query: Query<&Transform, With<NightPlayer>>,
+    mut rng: ResMut<GameRng>,
 ) {
     let Ok(player_transform) = player_query.get_single() else {
         return;
@@ -84,44 +199,169 @@ fn spawn_enemies(
     };
 
     let cur_time = time.elapsed_secs();
-    let mut rng = Rng::new();
-
-    let material = materials.add(Color::srgb(7.0, 0.2, 0.2));
-
-    let radius = 7.0;
-    let mesh = meshes.add(Circle::new(radius));
+    let day_spawn_rate = campaign.day(player_stats.day, &difficulty).spawn_rate();
 
     for (enemy_spawner, mut last_spawn_time, transform) in spawner_query.iter_mut() {
-        if last_spawn_time.0 + enemy_spawner.spawn_rate.recip() <= cur_time {
+        // Sleeping more intensely makes the nests more active.
+        let spawn_rate = enemy_spawner.spawn_rate
+            * player_stats.sleep_intensity
+            * mutators.spawn_rate()
+            * day_spawn_rate;
+        if last_spawn_time.0 + spawn_rate.recip() <= cur_time {
+            **last_spawn_time = cur_time;
+
             let rx = rng.f32();
             let ry = rng.f32();
-            let pos = transform.translation.truncate()
+            let mut pos = transform.translation.truncate()
                 + vec2(
                     rx * enemy_spawner.radius * 2.0 - enemy_spawner.radius,
                     ry * enemy_spawner.radius * 2.0 - enemy_spawner.radius,
                 );
 
-            let direction = (player_transform.translation.truncate() - pos).normalize();
+            // Don't spawn enemies right on top of the player. Nests the player is
+            // standing next to push their enemies out to the far side instead, or
+            // further around the player when that's in a wall or the furniture.
+            let player_position = player_transform.translation.truncate();
+            if pos.distance(player_position) < rules.min_spawn_distance {
+                let direction = (transform.translation.truncate() - player_position)
+                    .try_normalize()
+                    .unwrap_or_else(|| Vec2::from_angle(rng.f32() * std::f32::consts::TAU));
+                let pushed = (0..PUSH_DIRECTIONS)
+                    .map(|i| {
+                        let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+                        let turn = side * ((i + 1) / 2) as f32 * std::f32::consts::TAU
+                            / PUSH_DIRECTIONS as f32;
+                        player_position
+                            + Vec2::from_angle(turn).rotate(direction) * rules.min_spawn_distance
+                    })
+                    .find(|&point| {
+                        layout
+                            .as_ref()
+                            .is_none_or(|layout| layout.is_free(point, ENEMY_RADIUS))
+                    });
+                let Some(pushed) = pushed else {
+                    continue;
+                };
+                pos = pushed;
+            }
+
             commands.spawn((
-                Enemy,
+                SpawnTelegraph {
+                    spawn_type: enemy_spawner.spawn_type,
+                    spawn_at: cur_time + rules.spawn_delay,
+                },
                 StateScoped(GameState::NightTime),
                 Transform::from_translation(pos.extend(0.0)),
-                Mesh2d(mesh.clone()),
-                Collider::circle(radius),
-                CollisionLayers::new(GameLayer::Enemy, [GameLayer::Default, GameLayer::Player]),
-                RigidBody::Dynamic,
-                MeshMaterial2d(material.clone()),
-                LinearVelocity(rules.base_speed * direction),
+                effects.spawn_marker_effect.clone(),
             ));
-            **last_spawn_time = cur_time;
         }
     }
 }
 
+fn hatch_telegraphs(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+    telegraph_query: Query<(Entity, &SpawnTelegraph, &Transform)>,
+    rules: Res<Assets<EnemyRules>>,
+    elite_rules: Res<Assets<EliteRules>>,
+    level: Res<Level>,
+    level_state: Res<LevelState>,
+    player_stats: Res<PlayerStats>,
+    difficulty: Res<Difficulty>,
+    player_query: Query<&Transform, With<NightPlayer>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+    let elite_rules = elite_rules.get(&level.elites);
+
+    let cur_time = time.elapsed_secs();
+
+    for (telegraph_entity, telegraph, transform) in telegraph_query.iter() {
+        if telegraph.spawn_at > cur_time {
+            continue;
+        }
+        commands.entity(telegraph_entity).despawn_recursive();
+
+        let pos = transform.translation.truncate();
+        let direction = (player_transform.translation.truncate() - pos).normalize_or_zero();
+        let mut enemy = spawn_regular_enemy(
+            &mut commands,
+            telegraph.spawn_type,
+            pos,
+            &game_assets,
+            &difficulty,
+        );
+        match telegraph.spawn_type {
+            EnemyType::Ranged => {
+                enemy.insert((
+                    RangedEnemy {
+                        last_shot: cur_time,
+                        strafe_direction: if rng.bool() { 1.0 } else { -1.0 },
+                    },
+                    LinearVelocity(rules.ranged.speed * direction),
+                ));
+            }
+            _ => {
+                enemy.insert(LinearVelocity(rules.base_speed * direction));
+            }
+        }
+        if let Some(affix) = elite_rules.and_then(|elite_rules| {
+            elite_rules.roll(&mut rng, player_stats.day, level_state.in_nightmare())
+        }) {
+            make_elite(&mut enemy, affix, &game_assets, &difficulty);
+        }
+    }
+}
+
+/// Spawns an enemy that isn't a boss, with the health the difficulty gives it.
+/// Its velocity, and anything else its type needs, is up to the caller.
+pub(crate) fn spawn_regular_enemy<'a>(
+    commands: &'a mut Commands,
+    spawn_type: EnemyType,
+    pos: Vec2,
+    game_assets: &GameAssets,
+    difficulty: &Difficulty,
+) -> EntityCommands<'a> {
+    let (mesh, material) = match spawn_type {
+        EnemyType::Ranged => (&game_assets.ranged_mesh, &game_assets.ranged_material),
+        _ => (&game_assets.enemy_mesh, &game_assets.enemy_material),
+    };
+    let mut enemy = commands.spawn((
+        spawn_type,
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(pos.extend(0.0)),
+        Mesh2d(mesh.clone()),
+        MeshMaterial2d(material.clone()),
+        Collider::circle(ENEMY_RADIUS),
+        CollisionLayers::new(GameLayer::Enemy, [GameLayer::Default, GameLayer::Player]),
+        RigidBody::Dynamic,
+    ));
+    // Regular enemies go down in one shot, unless the difficulty says otherwise.
+    if difficulty.enemy_health > 1.0 {
+        enemy.insert(EnemyHealth::new(difficulty.enemy_health));
+    }
+    enemy
+}
+
 fn handle_collisions(
     mut commands: Commands,
     mut collision_event_reader: EventReader<Collision>,
-    enemies: Query<(Entity, &GlobalTransform), With<Enemy>>,
+    mut enemies: Query<
+        (
+            &GlobalTransform,
+            &EnemyType,
+            Option<&Elite>,
+            Option<&mut EnemyHealth>,
+        ),
+        With<Enemy>,
+    >,
     mut enemy_died_writer: EventWriter<EnemyDiedEvent>,
     player: Query<Entity, With<NightPlayer>>,
     shots: Query<Entity, With<PlayerShot>>,
@@ -131,24 +371,56 @@ fn handle_collisions(
     };
     for Collision(contacts) in collision_event_reader.read() {
         if contacts.collision_started() {
-            let Ok((enemy_entity, enemy_transform)) = enemies
-                .get(contacts.entity1)
-                .or_else(|_| enemies.get(contacts.entity2))
+            let enemy_entity = if enemies.contains(contacts.entity1) {
+                contacts.entity1
+            } else if enemies.contains(contacts.entity2) {
+                contacts.entity2
+            } else {
+                continue;
+            };
+            let Ok((enemy_transform, &enemy_type, elite, health)) = enemies.get_mut(enemy_entity)
             else {
                 continue;
             };
 
             let killed = !(contacts.entity1 == player || contacts.entity2 == player);
 
-            if shots.get(contacts.entity1).is_ok() {
+            let shot = if shots.get(contacts.entity1).is_ok() {
                 commands.entity(contacts.entity1).despawn_recursive();
+                true
             } else if shots.get(contacts.entity2).is_ok() {
                 commands.entity(contacts.entity2).despawn_recursive();
+                true
+            } else {
+                false
+            };
+
+            // Bumping into walls and other scenery doesn't hurt anyone.
+            if killed && !shot {
+                continue;
+            }
+
+            if let Some(mut health) = health {
+                if shot {
+                    // Only die once, even if hit by several shots in the same frame.
+                    if **health <= 0.0 {
+                        continue;
+                    }
+                    **health -= 1.0;
+                    if **health > 0.0 {
+                        continue;
+                    }
+                } else if matches!(enemy_type, EnemyType::Spawner | EnemyType::Boss) {
+                    // Nests and bosses aren't destroyed by running into the player.
+                    continue;
+                }
             }
 
             enemy_died_writer.send(EnemyDiedEvent {
                 entity: enemy_entity,
-                transform: enemy_transform.clone(),
+                transform: *enemy_transform,
+                enemy_type,
+                elite: elite.copied(),
                 killed,
             });
         }
@@ -156,9 +428,20 @@ fn handle_collisions(
 }
 
 fn target_enemies(
-    mut enemy_query: Query<(&mut LinearVelocity, &GlobalTransform), With<Enemy>>,
+    mut enemy_query: Query<
+        (&mut LinearVelocity, &GlobalTransform, Option<&Elite>),
+        (
+            With<Enemy>,
+            Without<RangedEnemy>,
+            Without<EnemySpawner>,
+            Without<Boss>,
+        ),
+    >,
     player_query: Query<&GlobalTransform, With<NightPlayer>>,
     time: Res<Time>,
+    phase: Res<SleepPhase>,
+    mutators: Res<Mutators>,
+    difficulty: Res<Difficulty>,
     rules: Res<Assets<EnemyRules>>,
     level: Res<Level>,
 ) {
@@ -170,46 +453,233 @@ fn target_enemies(
         return;
     };
 
-    let speed = rules.base_speed;
+    let speed =
+        rules.base_speed * phase.enemy_speed() * mutators.enemy_speed() * difficulty.enemy_speed;
 
-    for (mut velocity, enemy_transform) in enemy_query.iter_mut() {
+    for (mut velocity, enemy_transform, elite) in enemy_query.iter_mut() {
         let direction = (player_transform.translation().truncate()
             - enemy_transform.translation().truncate())
         .normalize();
         **velocity += direction * time.delta_secs() * 100.0;
-        velocity.0 = velocity.0.clamp_length_max(speed);
+        velocity.0 = velocity
+            .0
+            .clamp_length_max(speed * elite.map_or(1.0, Elite::speed_multiplier));
+    }
+}
+
+fn ranged_enemies(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    mut enemy_query: Query<(
+        &mut RangedEnemy,
+        &mut LinearVelocity,
+        &GlobalTransform,
+        Option<&Elite>,
+    )>,
+    player_query: Query<&GlobalTransform, With<NightPlayer>>,
+    time: Res<Time>,
+    phase: Res<SleepPhase>,
+    mutators: Res<Mutators>,
+    difficulty: Res<Difficulty>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+    let rules = &rules.ranged;
+
+    let cur_time = time.elapsed_secs();
+
+    for (mut ranged, mut velocity, enemy_transform, elite) in enemy_query.iter_mut() {
+        let offset =
+            player_transform.translation().truncate() - enemy_transform.translation().truncate();
+        let distance = offset.length();
+        let direction = offset.normalize_or_zero();
+
+        // Approach or back off until we're roughly at the preferred distance, and
+        // strafe around the player while there.
+        let radial = if distance > rules.preferred_distance * 1.1 {
+            1.0
+        } else if distance < rules.preferred_distance * 0.9 {
+            -1.0
+        } else {
+            0.0
+        };
+        if rng.f32() < time.delta_secs() * 0.5 {
+            ranged.strafe_direction = -ranged.strafe_direction;
+        }
+        let strafe = direction.perp() * ranged.strafe_direction;
+        velocity.0 = (direction * radial + strafe).normalize_or_zero()
+            * rules.speed
+            * phase.enemy_speed()
+            * mutators.enemy_speed()
+            * difficulty.enemy_speed
+            * elite.map_or(1.0, Elite::speed_multiplier);
+
+        if ranged.last_shot + rules.fire_rate.recip() <= cur_time {
+            commands.spawn((
+                EnemyProjectile {
+                    damage: rules.damage,
+                },
+                StateScoped(GameState::NightTime),
+                Timed(3.0),
+                Transform::from_translation(enemy_transform.translation()),
+                Mesh2d(game_assets.enemy_projectile_mesh.clone()),
+                Collider::circle(ENEMY_PROJECTILE_RADIUS),
+                Sensor,
+                CollisionLayers::new(
+                    GameLayer::EnemyProjectile,
+                    [GameLayer::Default, GameLayer::Player],
+                ),
+                RigidBody::Dynamic,
+                MeshMaterial2d(game_assets.enemy_projectile_material.clone()),
+                LinearVelocity(direction * rules.projectile_speed),
+            ));
+            ranged.last_shot = cur_time;
+        }
     }
 }
 
+fn handle_enemy_projectiles(
+    mut commands: Commands,
+    mut collision_event_reader: EventReader<Collision>,
+    projectiles: Query<&EnemyProjectile>,
+    mut player: Query<(Entity, &mut NightPlayer)>,
+    mut shake: ResMut<ScreenShake>,
+    mut player_damaged_writer: EventWriter<PlayerDamaged>,
+    phase: Res<SleepPhase>,
+) {
+    let Ok((player_entity, mut player)) = player.get_single_mut() else {
+        return;
+    };
+    for Collision(contacts) in collision_event_reader.read() {
+        if contacts.collision_started() {
+            let (projectile_entity, other) = if projectiles.contains(contacts.entity1) {
+                (contacts.entity1, contacts.entity2)
+            } else if projectiles.contains(contacts.entity2) {
+                (contacts.entity2, contacts.entity1)
+            } else {
+                continue;
+            };
+
+            if other == player_entity {
+                if let Ok(projectile) = projectiles.get(projectile_entity) {
+                    let amount = projectile.damage * phase.damage_taken();
+                    player.health -= amount;
+                    player_damaged_writer.send(PlayerDamaged { amount });
+                    shake.add_trauma(0.3);
+                }
+            }
+            commands.entity(projectile_entity).despawn_recursive();
+        }
+    }
+}
+
+fn drift_spawners(
+    mut spawner_query: Query<
+        (&mut LinearVelocity, &GlobalTransform),
+        (With<EnemySpawner>, Without<FixedSpawner>),
+    >,
+    time: Res<Time>,
+    rules: Res<Assets<EnemyRules>>,
+    arenas: Res<Assets<Arena>>,
+    level: Res<Level>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+    let arena = arenas.get(&level.arena);
+
+    for (mut velocity, transform) in spawner_query.iter_mut() {
+        let position = transform.translation().truncate();
+        // Nests are kinematic, so steer them back into the room instead of relying on the walls.
+        if let Some(arena) = arena.filter(|arena| !arena.contains(position)) {
+            velocity.0 = (arena.bounds().center() - position).normalize_or_zero()
+                * rules.spawner.drift_speed;
+        } else if velocity.0 == Vec2::ZERO || rng.f32() < time.delta_secs() * 0.2 {
+            // Occasionally pick a new random heading.
+            velocity.0 =
+                Vec2::from_angle(rng.f32() * std::f32::consts::TAU) * rules.spawner.drift_speed;
+        }
+    }
+}
+
+const COMFORT_DROP_CHANCE: f32 = 0.05;
+const MAGNET_DROP_CHANCE: f32 = 0.01;
+
 fn handle_enemy_death(
     mut commands: Commands,
     mut player_query: Query<&mut NightPlayer>,
-    mut player_stats: ResMut<PlayerStats>,
     mut enemy_died_event_reader: EventReader<EnemyDiedEvent>,
+    mut shake: ResMut<ScreenShake>,
+    mut player_damaged_writer: EventWriter<PlayerDamaged>,
+    phase: Res<SleepPhase>,
     effects: Res<Effects>,
+    game_assets: Res<GameAssets>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+    mut rng: ResMut<GameRng>,
 ) {
     let Ok(mut player) = player_query.get_single_mut() else {
         return;
     };
 
+    let rules = rules.get(&level.rules);
+
     for &EnemyDiedEvent {
         entity,
         transform,
+        enemy_type,
+        elite,
         killed,
     } in enemy_died_event_reader.read()
     {
         commands.entity(entity).despawn_recursive();
+
+        let (effect, lifetime) = match enemy_type {
+            EnemyType::Spawner | EnemyType::Boss => (effects.nest_death_effect.clone(), 2.5),
+            _ => (effects.death_effect.clone(), 0.5),
+        };
         commands.spawn((
             StateScoped(GameState::NightTime),
-            Timed(0.5),
+            Timed(lifetime),
             Transform::from_translation(transform.translation()),
-            effects.death_effect.clone(),
+            effect,
         ));
 
         if killed {
-            player_stats.unsafe_rest += 5;
+            let rest = match (enemy_type, rules) {
+                (EnemyType::Spawner, Some(rules)) => rules.spawner.rest_bonus,
+                (EnemyType::Boss, Some(rules)) => rules.boss.rest_bonus,
+                _ => 5,
+            };
+            let multiplier = elite.map_or(1.0, |elite| elite.rest_multiplier);
+            let position = transform.translation();
+            spawn_pickup(
+                &mut commands,
+                &game_assets,
+                Pickup::Rest((rest as f32 * multiplier) as u32),
+                position,
+            );
+
+            let roll = rng.f32();
+            if roll < MAGNET_DROP_CHANCE {
+                spawn_pickup(&mut commands, &game_assets, Pickup::Magnet, position);
+            } else if roll < MAGNET_DROP_CHANCE + COMFORT_DROP_CHANCE {
+                spawn_pickup(&mut commands, &game_assets, Pickup::Comfort(1.0), position);
+            }
         } else {
-            player.health -= 1.0;
+            let amount = phase.damage_taken();
+            player.health -= amount;
+            player_damaged_writer.send(PlayerDamaged { amount });
+            shake.add_trauma(0.4);
         }
     }
 }
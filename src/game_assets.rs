@@ -0,0 +1,155 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    elite::{AffixKind, EliteRules},
+    enemy::EnemyRules,
+    night::Level,
+};
+
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameAssets>();
+        app.add_systems(Update, (create_elite_materials, create_boss_mesh));
+    }
+}
+
+pub const ENEMY_RADIUS: f32 = 7.0;
+pub const MINION_RADIUS: f32 = 5.0;
+pub const SPAWNER_SIZE: f32 = 20.0;
+pub const PLAYER_SHOT_RADIUS: f32 = 3.0;
+pub const ENEMY_PROJECTILE_RADIUS: f32 = 3.0;
+pub const BOSS_PROJECTILE_RADIUS: f32 = 4.0;
+
+/// Colors of the boss in each of its phases.
+const BOSS_COLORS: &[Color] = &[
+    Color::srgb(3.0, 0.5, 6.0),
+    Color::srgb(6.0, 0.5, 4.0),
+    Color::srgb(8.0, 0.5, 0.5),
+];
+
+/// Mesh and material handles shared by everything spawned during the night, so we
+/// don't allocate new assets every time something spawns.
+#[derive(Resource)]
+pub struct GameAssets {
+    pub enemy_mesh: Handle<Mesh>,
+    pub enemy_material: Handle<ColorMaterial>,
+    pub ranged_mesh: Handle<Mesh>,
+    pub ranged_material: Handle<ColorMaterial>,
+    pub minion_mesh: Handle<Mesh>,
+    pub spawner_mesh: Handle<Mesh>,
+    pub spawner_material: Handle<ColorMaterial>,
+    /// Sized by the boss rules, so created once `enemies.ron` has loaded.
+    pub boss_mesh: Option<Handle<Mesh>>,
+    pub boss_materials: Vec<Handle<ColorMaterial>>,
+    pub player_shot_mesh: Handle<Mesh>,
+    pub player_shot_material: Handle<ColorMaterial>,
+    pub enemy_projectile_mesh: Handle<Mesh>,
+    pub enemy_projectile_material: Handle<ColorMaterial>,
+    pub boss_projectile_mesh: Handle<Mesh>,
+    pub boss_projectile_material: Handle<ColorMaterial>,
+    pub pickup_mesh: Handle<Mesh>,
+    pub rest_material: Handle<ColorMaterial>,
+    pub comfort_material: Handle<ColorMaterial>,
+    pub magnet_material: Handle<ColorMaterial>,
+    pub elite_outline_mesh: Handle<Mesh>,
+    /// A unit square, scaled to the size of each wall.
+    pub wall_mesh: Handle<Mesh>,
+    pub wall_material: Handle<ColorMaterial>,
+    pub bed_material: Handle<ColorMaterial>,
+    pub nightstand_material: Handle<ColorMaterial>,
+    pub rug_material: Handle<ColorMaterial>,
+    pub toy_material: Handle<ColorMaterial>,
+    /// Outline materials per affix, created once `elites.ron` has loaded.
+    pub elite_materials: HashMap<AffixKind, Handle<ColorMaterial>>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        let enemy_mesh = meshes.add(Circle::new(ENEMY_RADIUS));
+        let ranged_mesh = meshes.add(RegularPolygon::new(ENEMY_RADIUS, 3));
+        let minion_mesh = meshes.add(Circle::new(MINION_RADIUS));
+        let spawner_mesh = meshes.add(Rectangle::new(SPAWNER_SIZE, SPAWNER_SIZE));
+        let player_shot_mesh = meshes.add(Circle::new(PLAYER_SHOT_RADIUS));
+        let enemy_projectile_mesh = meshes.add(Circle::new(ENEMY_PROJECTILE_RADIUS));
+        let boss_projectile_mesh = meshes.add(Circle::new(BOSS_PROJECTILE_RADIUS));
+        let pickup_mesh = meshes.add(Rhombus::new(8.0, 8.0));
+        let elite_outline_mesh = meshes.add(Circle::new(ENEMY_RADIUS + 3.0));
+        let wall_mesh = meshes.add(Rectangle::new(1.0, 1.0));
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self {
+            enemy_mesh,
+            enemy_material: materials.add(Color::srgb(7.0, 0.2, 0.2)),
+            ranged_mesh,
+            ranged_material: materials.add(Color::srgb(7.0, 3.0, 0.2)),
+            minion_mesh,
+            spawner_mesh,
+            spawner_material: materials.add(Color::srgb(1.0, 1.0, 1.0)),
+            boss_mesh: None,
+            boss_materials: BOSS_COLORS
+                .iter()
+                .map(|&color| materials.add(color))
+                .collect(),
+            player_shot_mesh,
+            player_shot_material: materials.add(Color::srgb(0.0, 0.2, 10.2)),
+            enemy_projectile_mesh,
+            enemy_projectile_material: materials.add(Color::srgb(7.0, 3.0, 0.2)),
+            boss_projectile_mesh,
+            boss_projectile_material: materials.add(Color::srgb(6.0, 0.5, 6.0)),
+            pickup_mesh,
+            rest_material: materials.add(Color::srgb(0.5, 0.5, 6.0)),
+            comfort_material: materials.add(Color::srgb(6.0, 0.5, 1.5)),
+            magnet_material: materials.add(Color::srgb(6.0, 6.0, 6.0)),
+            elite_outline_mesh,
+            wall_mesh,
+            wall_material: materials.add(Color::srgb(0.3, 0.25, 0.6)),
+            bed_material: materials.add(Color::srgb(0.25, 0.2, 0.45)),
+            nightstand_material: materials.add(Color::srgb(0.35, 0.25, 0.2)),
+            rug_material: materials.add(Color::srgb(0.12, 0.1, 0.2)),
+            toy_material: materials.add(Color::srgb(0.6, 0.4, 0.7)),
+            elite_materials: HashMap::default(),
+        }
+    }
+}
+
+fn create_elite_materials(
+    mut game_assets: ResMut<GameAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    rules: Res<Assets<EliteRules>>,
+    level: Res<Level>,
+) {
+    if !game_assets.elite_materials.is_empty() {
+        return;
+    }
+
+    let Some(rules) = rules.get(&level.elites) else {
+        return;
+    };
+
+    for affix in &rules.affixes {
+        let (r, g, b) = affix.color;
+        game_assets
+            .elite_materials
+            .insert(affix.kind, materials.add(Color::srgb(r, g, b)));
+    }
+}
+
+fn create_boss_mesh(
+    mut game_assets: ResMut<GameAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    rules: Res<Assets<EnemyRules>>,
+    level: Res<Level>,
+) {
+    if game_assets.boss_mesh.is_some() {
+        return;
+    }
+
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    game_assets.boss_mesh = Some(meshes.add(Circle::new(rules.boss.radius)));
+}
@@ -0,0 +1,96 @@
+//! Running the game without a window or renderer, for tests and balance
+//! simulations. Time advances by a fixed step every update, so a night plays out
+//! as fast as the CPU allows.
+
+use std::time::Duration;
+
+use avian2d::prelude::PhysicsPlugins;
+use bevy::{input::InputPlugin, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+use bevy_hanabi::EffectAsset;
+use vleue_navigator::NavMesh;
+
+use crate::{
+    achievements::AchievementProgress, arena::Arena, elite::EliteRules, enemy::EnemyRules,
+    journal::DreamJournal, scores::HighScores, GamePlugins, GameState,
+};
+
+/// Length of a simulated frame.
+pub const FRAME: f32 = 1.0 / 60.0;
+
+/// An app with the game and physics plugins, but no window, renderer or audio.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        StatesPlugin,
+        TransformPlugin,
+        HierarchyPlugin,
+        InputPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_asset::<EffectAsset>()
+    .init_asset::<NavMesh>()
+    .add_plugins((
+        PhysicsPlugins::default().with_length_unit(20.0),
+        GamePlugins,
+    ))
+    .init_state::<GameState>()
+    .enable_state_scoped_entities::<GameState>()
+    .insert_resource(ClearColor::default())
+    // Keep simulated runs out of the player's profile.
+    .insert_resource(DreamJournal::default())
+    .insert_resource(AchievementProgress::default())
+    .insert_resource(HighScores::default())
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        FRAME,
+    )));
+    app
+}
+
+/// Handles to the level data, kept alive so it's only loaded once.
+#[derive(Resource)]
+struct PreloadedAssets {
+    rules: Handle<EnemyRules>,
+    elites: Handle<EliteRules>,
+    arena: Handle<Arena>,
+}
+
+/// Loads the level data and blocks until it's ready, so the first night doesn't
+/// start with several seconds of simulated time without enemies. Returns whether
+/// everything loaded.
+pub fn preload_level_assets(app: &mut App) -> bool {
+    let asset_server = app.world().resource::<AssetServer>();
+    let preloaded = PreloadedAssets {
+        rules: asset_server.load("enemies.ron"),
+        elites: asset_server.load("elites.ron"),
+        arena: asset_server.load("arena.ron"),
+    };
+    app.insert_resource(preloaded);
+
+    // Keep the game clock still while waiting on the files.
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    let mut loaded = false;
+    for _ in 0..1000 {
+        loaded = level_assets_loaded(app.world());
+        if loaded {
+            break;
+        }
+        app.update();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    app.world_mut().resource_mut::<Time<Virtual>>().unpause();
+    loaded
+}
+
+fn level_assets_loaded(world: &World) -> bool {
+    let preloaded = world.resource::<PreloadedAssets>();
+    world
+        .resource::<Assets<EnemyRules>>()
+        .contains(&preloaded.rules)
+        && world
+            .resource::<Assets<EliteRules>>()
+            .contains(&preloaded.elites)
+        && world.resource::<Assets<Arena>>().contains(&preloaded.arena)
+}
@@ -0,0 +1,427 @@
+//! The dream journal, which carries progress over from one run to the next. Every
+//! run ends by writing down some dreams, which are spent between runs on entries
+//! that add upgrades to the shop or make the next runs start out stronger.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{color::palettes::tailwind, prelude::*};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    daily::{spawn_daily_link, RunMode},
+    day::{
+        button_hover_effect_out, button_hover_effect_over, find_upgrade, new_day, new_game,
+        Upgrade, DREAM_UPGRADES, UPGRADES,
+    },
+    difficulty::{spawn_difficulty_picker, Difficulty},
+    player::PlayerStats,
+    replay::Replay,
+    scores::HighScores,
+    stats::RunStats,
+    GameState,
+};
+
+pub struct JournalPlugin;
+
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DreamJournal::load(PROFILE_PATH));
+        app.add_systems(
+            OnEnter(GameState::DayTime),
+            apply_journal_entries.before(new_day),
+        );
+        app.add_systems(OnEnter(GameState::GameOver), award_dreams);
+        app.add_systems(OnEnter(GameState::GameWon), award_dreams);
+        app.add_systems(OnEnter(GameState::Journal), spawn_journal);
+        app.add_systems(Update, update_journal.run_if(in_state(GameState::Journal)));
+    }
+}
+
+/// Where the journal is kept, apart from recordings of single runs.
+pub const PROFILE_PATH: &str = "profile.json";
+
+/// What an entry in the journal does once it's unlocked.
+#[derive(Clone, Copy)]
+pub enum JournalReward {
+    /// Adds one of the [`DREAM_UPGRADES`] to the shop.
+    Upgrade(&'static str),
+    /// Extra rest to spend on the first day.
+    StartingRest(u32),
+    /// An upgrade every run starts out with.
+    StartingItem(&'static str),
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct JournalEntry {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub cost: u32,
+    pub reward: JournalReward,
+}
+
+pub const JOURNAL_ENTRIES: &[JournalEntry] = &[
+    JournalEntry {
+        name: "Heavy dreams",
+        description: "Weighted blankets in the shop",
+        cost: 5,
+        reward: JournalReward::Upgrade("Weighted blanket"),
+    },
+    JournalEntry {
+        name: "Static",
+        description: "White noise machines in the shop",
+        cost: 4,
+        reward: JournalReward::Upgrade("White noise machine"),
+    },
+    JournalEntry {
+        name: "Purple fields",
+        description: "Lavender spray in the shop",
+        cost: 3,
+        reward: JournalReward::Upgrade("Lavender spray"),
+    },
+    JournalEntry {
+        name: "Well rested",
+        description: "Start with 100 more rest",
+        cost: 3,
+        reward: JournalReward::StartingRest(100),
+    },
+    JournalEntry {
+        name: "Deeply rested",
+        description: "Start with 200 more rest",
+        cost: 8,
+        reward: JournalReward::StartingRest(200),
+    },
+    JournalEntry {
+        name: "Old friend",
+        description: "Start with a fluffy pillow",
+        cost: 6,
+        reward: JournalReward::StartingItem("Fluffy pillow"),
+    },
+    JournalEntry {
+        name: "Warm milk",
+        description: "Start with milk and cookies",
+        cost: 6,
+        reward: JournalReward::StartingItem("Milk and cookies"),
+    },
+];
+
+/// Dreams for finishing a run: one for every night slept, one for every 25
+/// monsters beaten and a few extra for winning.
+pub fn dreams_earned(run_stats: &RunStats, won: bool) -> u32 {
+    let kills: u32 = run_stats.nights.iter().map(|night| night.kills).sum();
+    run_stats.nights.len() as u32 + kills / 25 + if won { 5 } else { 0 }
+}
+
+/// Progress that is kept between runs.
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct DreamJournal {
+    /// Dreams left to spend.
+    pub dreams: u32,
+    /// Names of the unlocked [`JOURNAL_ENTRIES`].
+    pub unlocked: Vec<String>,
+    /// The difficulty new runs are played on.
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Dreams written down at the end of the last run.
+    #[serde(skip)]
+    pub last_award: u32,
+    /// Where the journal is saved, or `None` to keep it in memory only.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl DreamJournal {
+    /// Reads the journal from `path`, starting a new one if there isn't one yet.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self {
+            path: Some(path.to_owned()),
+            ..load_profile_file(path)
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            save_profile_file(path, self);
+        }
+    }
+
+    pub fn is_unlocked(&self, entry: &JournalEntry) -> bool {
+        self.unlocked.iter().any(|name| name == entry.name)
+    }
+
+    /// Spends dreams on `entry`, returning whether there were enough.
+    pub fn unlock(&mut self, entry: &JournalEntry) -> bool {
+        if self.is_unlocked(entry) || self.dreams < entry.cost {
+            return false;
+        }
+
+        self.dreams -= entry.cost;
+        self.unlocked.push(entry.name.to_string());
+        true
+    }
+
+    fn rewards(&self) -> impl Iterator<Item = JournalReward> + '_ {
+        JOURNAL_ENTRIES
+            .iter()
+            .filter(|entry| self.is_unlocked(entry))
+            .map(|entry| entry.reward)
+    }
+
+    /// The upgrades for sale in the shop.
+    pub fn shop_upgrades(&self) -> impl Iterator<Item = &'static Upgrade> + '_ {
+        let unlocked = DREAM_UPGRADES.iter().filter(|upgrade| {
+            self.rewards().any(
+                |reward| matches!(reward, JournalReward::Upgrade(name) if name == upgrade.name),
+            )
+        });
+        UPGRADES.iter().chain(unlocked)
+    }
+
+    /// Gives a new run its starting rest and items.
+    pub fn apply_starting_rewards(&self, player_stats: &mut PlayerStats) {
+        for reward in self.rewards() {
+            match reward {
+                JournalReward::Upgrade(_) => {}
+                JournalReward::StartingRest(rest) => player_stats.rest += rest,
+                JournalReward::StartingItem(name) => {
+                    if let Some(upgrade) = find_upgrade(name) {
+                        (upgrade.effect)(player_stats);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reads a file that is kept between runs, or starts from scratch if there isn't one.
+/// A file that can't be read is moved aside first, so saving the new one doesn't
+/// lose the progress in it.
+pub(crate) fn load_profile_file<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            let backup = unreadable_profile_path(path);
+            warn!(
+                "Unreadable profile {}: {err}, moving it to {}",
+                path.display(),
+                backup.display()
+            );
+            if let Err(err) = std::fs::rename(path, &backup) {
+                warn!("Failed to move {}: {err}", path.display());
+            }
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+/// Where an unreadable profile file is kept, named after when it was found so
+/// older ones aren't replaced.
+fn unreadable_profile_path(path: &Path) -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{name}.{secs}.unreadable"))
+}
+
+pub(crate) fn save_profile_file<T: Serialize>(path: &Path, value: &T) {
+    let result = serde_json::to_vec_pretty(value)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(path, json));
+    if let Err(err) = result {
+        warn!("Failed to save profile to {}: {err}", path.display());
+    }
+}
+
+fn apply_journal_entries(
+    journal: Res<DreamJournal>,
+    run_mode: Res<RunMode>,
+    mut player_stats: ResMut<PlayerStats>,
+) {
+    // Only at the start of a run, before the first day. Daily Dreams start the
+    // same for everyone.
+    if player_stats.day == 0 && *run_mode == RunMode::Normal {
+        journal.apply_starting_rewards(&mut player_stats);
+    }
+}
+
+pub(crate) fn award_dreams(
+    mut journal: ResMut<DreamJournal>,
+    run_stats: Res<RunStats>,
+    state: Res<State<GameState>>,
+    replay: Option<Res<Replay>>,
+) {
+    // A replayed run was paid out when it was played.
+    if replay.is_some() {
+        journal.last_award = 0;
+        return;
+    }
+
+    let dreams = dreams_earned(&run_stats, *state.get() == GameState::GameWon);
+    journal.dreams += dreams;
+    journal.last_award = dreams;
+    journal.save();
+}
+
+#[derive(Component)]
+struct JournalDreams;
+
+fn spawn_journal(mut commands: Commands, journal: Res<DreamJournal>, high_scores: Res<HighScores>) {
+    let mut menu = commands.spawn((
+        StateScoped(GameState::Journal),
+        Node {
+            flex_direction: FlexDirection::Column,
+            width: Val::Percent(90.0),
+            height: Val::Percent(90.0),
+            justify_self: JustifySelf::Center,
+            align_self: AlignSelf::Center,
+            ..default()
+        },
+        BackgroundColor(tailwind::INDIGO_300.into()),
+        PickingBehavior::IGNORE,
+    ));
+
+    menu.observe(|mut trigger: Trigger<Pointer<Click>>| {
+        trigger.propagate(false);
+    });
+
+    menu.with_children(|menu| {
+        menu.spawn((
+            Node {
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            Text::new("Dream journal"),
+            TextFont {
+                font_size: 32.0,
+                ..default()
+            },
+        ));
+        menu.spawn((
+            Node {
+                margin: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            Text::new(format!("{} dreams", journal.dreams)),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            JournalDreams,
+        ));
+
+        menu.spawn((
+            Node {
+                display: Display::Grid,
+                grid_template_columns: vec![GridTrack::px(200.0); 4],
+                grid_auto_rows: vec![GridTrack::px(80.0)],
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(tailwind::VIOLET_500.into()),
+        ))
+        .with_children(|entries| {
+            for entry in JOURNAL_ENTRIES {
+                entries
+                    .spawn((
+                        Node {
+                            margin: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        },
+                        BackgroundColor(if journal.is_unlocked(entry) {
+                            tailwind::GREEN_500.into()
+                        } else {
+                            tailwind::PINK_500.into()
+                        }),
+                        *entry,
+                    ))
+                    .with_children(|node| {
+                        node.spawn((
+                            Node {
+                                margin: UiRect::axes(Val::Px(10.0), Val::Px(5.0)),
+                                ..default()
+                            },
+                            Text::new(format!("{} ({} dreams)", entry.name, entry.cost)),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                        ));
+                        node.spawn((
+                            Node {
+                                margin: UiRect::axes(Val::Px(10.0), Val::Px(0.0)),
+                                ..default()
+                            },
+                            Text::new(entry.description),
+                            TextFont {
+                                font_size: 8.0,
+                                ..default()
+                            },
+                        ));
+                    })
+                    .observe(unlock_entry);
+            }
+        });
+
+        spawn_difficulty_picker(menu, &journal.difficulty);
+        spawn_daily_link(menu, &high_scores);
+
+        menu.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            },
+            Text::new("Start a new run"),
+            BackgroundColor(Color::NONE),
+        ))
+        .observe(button_hover_effect_over)
+        .observe(button_hover_effect_out)
+        .observe(new_game);
+    });
+}
+
+fn unlock_entry(
+    trigger: Trigger<Pointer<Click>>,
+    mut journal: ResMut<DreamJournal>,
+    mut entries: Query<(&JournalEntry, &mut BackgroundColor)>,
+) {
+    if trigger.button != PointerButton::Primary {
+        return;
+    }
+
+    if let Ok((entry, mut bg_color)) = entries.get_mut(trigger.entity()) {
+        if journal.unlock(entry) {
+            journal.save();
+        } else if !journal.is_unlocked(entry) {
+            bg_color.0 = tailwind::RED_500.into();
+        }
+    }
+}
+
+/// Shows which entries are unlocked and how many dreams are left.
+fn update_journal(
+    journal: Res<DreamJournal>,
+    mut entries: Query<(&JournalEntry, &mut BackgroundColor)>,
+    mut dreams_query: Query<&mut Text, With<JournalDreams>>,
+) {
+    if !journal.is_changed() {
+        return;
+    }
+
+    for (entry, mut bg_color) in entries.iter_mut() {
+        bg_color.0 = if journal.is_unlocked(entry) {
+            tailwind::GREEN_500.into()
+        } else {
+            tailwind::PINK_500.into()
+        };
+    }
+    for mut text in dreams_query.iter_mut() {
+        text.0 = format!("{} dreams", journal.dreams);
+    }
+}
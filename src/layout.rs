@@ -0,0 +1,439 @@
+use std::collections::VecDeque;
+
+use avian2d::prelude::{Collider, CollisionLayers, LayerMask, Position, RigidBody};
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+use vleue_navigator::NavMesh;
+
+use crate::{
+    arena::Arena,
+    enemy::EnemyType,
+    game_assets::GameAssets,
+    night::Level,
+    player::{NightPlayer, PlayerStats},
+    GameLayer, GameState,
+};
+
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<LevelFile>::new(&["level.ron"]));
+        app.add_systems(
+            FixedUpdate,
+            generate_level_layout.run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// Room the player needs to move around, roughly the radius of the player.
+pub const PLAYER_CLEARANCE: f32 = 15.0;
+/// Size of the grid cells used to check that the layout is connected.
+const CONNECTIVITY_CELL_SIZE: f32 = 10.0;
+/// How many times we try to place each piece of furniture before giving up on it.
+const PLACEMENT_ATTEMPTS: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FurnitureKind {
+    Bed,
+    Nightstand,
+    Rug,
+    Toy,
+}
+
+impl FurnitureKind {
+    /// Whether the furniture blocks movement. Rugs are just decoration.
+    pub fn blocking(self) -> bool {
+        !matches!(self, FurnitureKind::Rug)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Furniture {
+    pub kind: FurnitureKind,
+    pub rect: Rect,
+}
+
+/// A hand-authored level, loaded from `assets/levels/<name>.level.ron`.
+#[derive(Asset, TypePath, Serialize, Deserialize, Clone)]
+pub struct LevelFile {
+    pub arena: Arena,
+    pub player_start: (f32, f32),
+    pub obstacles: Vec<ObstacleDef>,
+    pub spawners: Vec<SpawnerDef>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ObstacleDef {
+    pub kind: FurnitureKind,
+    pub center: (f32, f32),
+    pub size: (f32, f32),
+}
+
+/// A nest that is placed when the night starts, on top of the usual waves.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SpawnerDef {
+    pub position: (f32, f32),
+    pub spawn_rate: f32,
+    pub spawn_type: EnemyType,
+}
+
+/// The furniture in the room for a single night.
+#[derive(Resource, Clone)]
+pub struct LevelLayout {
+    pub arena: Arena,
+    pub furniture: Vec<Furniture>,
+    pub player_start: Vec2,
+    /// Nests placed by a hand-authored level, generated layouts have none.
+    pub spawners: Vec<SpawnerDef>,
+}
+
+impl LevelLayout {
+    /// An empty room, with the player starting in the middle of it.
+    pub fn empty(arena: Arena) -> Self {
+        let center = arena.bounds().center();
+        let player_start = if arena.contains(center) {
+            center
+        } else {
+            arena.random_point(&mut Rng::with_seed(0), PLAYER_CLEARANCE)
+        };
+
+        Self {
+            arena,
+            furniture: Vec::new(),
+            player_start,
+            spawners: Vec::new(),
+        }
+    }
+
+    pub fn from_file(file: &LevelFile) -> Self {
+        let (x, y) = file.player_start;
+        Self {
+            arena: file.arena.clone(),
+            furniture: file
+                .obstacles
+                .iter()
+                .map(|obstacle| Furniture {
+                    kind: obstacle.kind,
+                    rect: Rect::from_center_size(obstacle.center.into(), obstacle.size.into()),
+                })
+                .collect(),
+            player_start: Vec2::new(x, y),
+            spawners: file.spawners.clone(),
+        }
+    }
+
+    /// Lays out the bedroom for the given run and day. The same seed and day
+    /// always give the same layout.
+    pub fn generate(arena: Arena, seed: u64, day: u32) -> Self {
+        let mut rng = Rng::with_seed(seed ^ (day as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let mut layout = Self::empty(arena);
+
+        // The bed goes against a wall, with a nightstand next to it.
+        if let Some(bed) = layout.place_bed(&mut rng) {
+            layout.place_nightstand(&mut rng, bed);
+        }
+
+        let bounds = layout.arena.bounds();
+        let rug_size = Vec2::new(rng.f32() * 100.0 + 200.0, rng.f32() * 60.0 + 140.0);
+        let rug_center = layout.arena.random_point(&mut rng, rug_size.max_element());
+        layout.furniture.push(Furniture {
+            kind: FurnitureKind::Rug,
+            rect: Rect::from_center_size(rug_center, rug_size),
+        });
+
+        // More toys on the floor the longer the week goes on.
+        for _ in 0..3 + day * 2 {
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let size = Vec2::new(rng.f32() * 25.0 + 15.0, rng.f32() * 25.0 + 15.0);
+                let center = Vec2::new(
+                    bounds.min.x + rng.f32() * bounds.width(),
+                    bounds.min.y + rng.f32() * bounds.height(),
+                );
+                if layout.try_place(FurnitureKind::Toy, Rect::from_center_size(center, size)) {
+                    break;
+                }
+            }
+        }
+        // Checking after every piece takes too long, so toys that block off part
+        // of the room are cleared away at the end.
+        layout.drop_until_connected();
+
+        layout
+    }
+
+    /// Furniture that blocks movement.
+    pub fn obstacles(&self) -> impl Iterator<Item = &Furniture> {
+        self.furniture
+            .iter()
+            .filter(|furniture| furniture.kind.blocking())
+    }
+
+    /// Whether a circle of radius `clearance` fits at `point`.
+    pub fn is_free(&self, point: Vec2, clearance: f32) -> bool {
+        self.arena.contains(point)
+            && self
+                .arena
+                .edges()
+                .all(|(a, b)| distance_to_segment(point, a, b) >= clearance)
+            && self
+                .obstacles()
+                .all(|obstacle| !obstacle.rect.inflate(clearance).contains(point))
+    }
+
+    /// A random point with at least `clearance` room around it.
+    pub fn random_free_point(&self, rng: &mut Rng, clearance: f32) -> Vec2 {
+        for _ in 0..100 {
+            let point = self.arena.random_point(rng, clearance);
+            if self.is_free(point, clearance) {
+                return point;
+            }
+        }
+        self.player_start
+    }
+
+    /// Whether every free spot in the room can be reached from the player start
+    /// by something with the given clearance.
+    pub fn is_connected(&self, clearance: f32) -> bool {
+        let bounds = self.arena.bounds();
+        let size = (bounds.size() / CONNECTIVITY_CELL_SIZE).ceil().as_uvec2();
+        let cell_center = |x: u32, y: u32| {
+            bounds.min + (Vec2::new(x as f32, y as f32) + 0.5) * CONNECTIVITY_CELL_SIZE
+        };
+
+        let mut free = vec![false; (size.x * size.y) as usize];
+        let mut free_count = 0;
+        for y in 0..size.y {
+            for x in 0..size.x {
+                if self.is_free(cell_center(x, y), clearance) {
+                    free[(y * size.x + x) as usize] = true;
+                    free_count += 1;
+                }
+            }
+        }
+
+        let start = ((self.player_start - bounds.min) / CONNECTIVITY_CELL_SIZE)
+            .floor()
+            .as_uvec2()
+            .min(size - 1);
+        let start_index = (start.y * size.x + start.x) as usize;
+        if !free[start_index] {
+            return false;
+        }
+
+        let mut visited = vec![false; free.len()];
+        visited[start_index] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut reached = 0;
+        while let Some(cell) = queue.pop_front() {
+            reached += 1;
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = cell.as_ivec2() + offset;
+                if next.x < 0 || next.y < 0 || next.x >= size.x as i32 || next.y >= size.y as i32 {
+                    continue;
+                }
+                let index = (next.y as u32 * size.x + next.x as u32) as usize;
+                if free[index] && !visited[index] {
+                    visited[index] = true;
+                    queue.push_back(next.as_uvec2());
+                }
+            }
+        }
+
+        reached == free_count
+    }
+
+    /// A navmesh of the room, with the furniture as obstacles.
+    pub fn navmesh(&self) -> NavMesh {
+        NavMesh::from_edge_and_obstacles(
+            self.arena.corners().collect(),
+            self.obstacles()
+                .map(|obstacle| {
+                    let Rect { min, max } = obstacle.rect;
+                    vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+                })
+                .collect(),
+        )
+    }
+
+    /// Removes the most recently placed furniture until every free spot can be
+    /// reached again.
+    fn drop_until_connected(&mut self) {
+        while !self.is_connected(PLAYER_CLEARANCE) {
+            let Some(last) = self
+                .furniture
+                .iter()
+                .rposition(|furniture| furniture.kind.blocking())
+            else {
+                return;
+            };
+            self.furniture.remove(last);
+        }
+    }
+
+    /// Adds the furniture if it fits in the room and doesn't overlap other
+    /// furniture. It may still cut the room in two.
+    fn try_place(&mut self, kind: FurnitureKind, rect: Rect) -> bool {
+        let corners = [
+            rect.min,
+            Vec2::new(rect.max.x, rect.min.y),
+            rect.max,
+            Vec2::new(rect.min.x, rect.max.y),
+        ];
+        if !corners.iter().all(|&corner| self.arena.contains(corner))
+            || rect
+                .inflate(PLAYER_CLEARANCE * 3.0)
+                .contains(self.player_start)
+            || self
+                .obstacles()
+                .any(|other| !other.rect.inflate(1.0).intersect(rect).is_empty())
+        {
+            return false;
+        }
+
+        self.furniture.push(Furniture { kind, rect });
+        true
+    }
+
+    fn place_bed(&mut self, rng: &mut Rng) -> Option<Rect> {
+        let size = Vec2::new(180.0, 280.0);
+        let walls: Vec<_> = self
+            .arena
+            .edges()
+            .filter(|(a, b)| (a.x == b.x || a.y == b.y) && a.distance(*b) > size.x + 20.0)
+            .collect();
+
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let &(a, b) = rng.choice(&walls)?;
+            let along = (b - a).normalize();
+            // The room is counter-clockwise, so the inside is to the left of each edge.
+            let inward = along.perp();
+            let t = rng.f32() * (a.distance(b) - size.x - 20.0) + size.x / 2.0 + 10.0;
+            // Leave a small gap to the wall, so the navmesh obstacle doesn't touch the edge.
+            let head = a + along * t + inward;
+            let foot = head + inward * size.y;
+            let rect = Rect::from_corners(
+                head - along.abs() * size.x / 2.0,
+                foot + along.abs() * size.x / 2.0,
+            );
+            if self.try_place(FurnitureKind::Bed, rect) {
+                if self.is_connected(PLAYER_CLEARANCE) {
+                    return Some(rect);
+                }
+                self.furniture.pop();
+            }
+        }
+        None
+    }
+
+    fn place_nightstand(&mut self, rng: &mut Rng, bed: Rect) {
+        let size = Vec2::splat(50.0);
+        let mut candidates = [
+            Vec2::new(bed.min.x - size.x / 2.0 - 2.0, bed.min.y + size.y / 2.0),
+            Vec2::new(bed.max.x + size.x / 2.0 + 2.0, bed.min.y + size.y / 2.0),
+            Vec2::new(bed.min.x - size.x / 2.0 - 2.0, bed.max.y - size.y / 2.0),
+            Vec2::new(bed.max.x + size.x / 2.0 + 2.0, bed.max.y - size.y / 2.0),
+        ];
+        rng.shuffle(&mut candidates);
+        for center in candidates {
+            if self.try_place(
+                FurnitureKind::Nightstand,
+                Rect::from_center_size(center, size),
+            ) {
+                return;
+            }
+        }
+    }
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+#[derive(Component)]
+pub struct FurniturePiece(pub FurnitureKind);
+
+/// A nest that belongs to the level, rather than one of the waves.
+#[derive(Component)]
+pub struct FixedSpawner;
+
+fn generate_level_layout(
+    mut commands: Commands,
+    layout: Option<Res<LevelLayout>>,
+    mut arenas: ResMut<Assets<Arena>>,
+    mut navmeshes: ResMut<Assets<NavMesh>>,
+    mut level: ResMut<Level>,
+    level_files: Res<Assets<LevelFile>>,
+    player_stats: Res<PlayerStats>,
+    game_assets: Res<GameAssets>,
+    mut player_query: Query<(&mut Transform, &mut Position), With<NightPlayer>>,
+) {
+    if layout.is_some() {
+        return;
+    }
+
+    let layout = if let Some(file) = &level.file {
+        let Some(file) = level_files.get(file) else {
+            return;
+        };
+        // The walls and camera follow the level's own room instead of `arena.ron`.
+        level.arena = arenas.add(file.arena.clone());
+        LevelLayout::from_file(file)
+    } else {
+        let Some(arena) = arenas.get(&level.arena) else {
+            return;
+        };
+        LevelLayout::generate(arena.clone(), player_stats.seed, player_stats.day)
+    };
+    spawn_layout(&mut commands, &layout, &game_assets);
+
+    navmeshes.insert(&level.navmesh, layout.navmesh());
+    for (mut transform, mut position) in player_query.iter_mut() {
+        transform.translation = layout.player_start.extend(transform.translation.z);
+        position.0 = layout.player_start;
+    }
+
+    commands.insert_resource(layout);
+}
+
+/// Spawns the furniture of a layout, with colliders for the blocking pieces.
+pub fn spawn_layout(commands: &mut Commands, layout: &LevelLayout, game_assets: &GameAssets) {
+    for furniture in &layout.furniture {
+        spawn_furniture(commands, furniture, game_assets);
+    }
+}
+
+pub fn spawn_furniture(
+    commands: &mut Commands,
+    furniture: &Furniture,
+    game_assets: &GameAssets,
+) -> Entity {
+    let (material, z) = match furniture.kind {
+        FurnitureKind::Bed => (game_assets.bed_material.clone(), -0.8),
+        FurnitureKind::Nightstand => (game_assets.nightstand_material.clone(), -0.8),
+        FurnitureKind::Rug => (game_assets.rug_material.clone(), -0.9),
+        FurnitureKind::Toy => (game_assets.toy_material.clone(), -0.8),
+    };
+
+    // Furniture uses a unit square mesh, scaled to its size.
+    let mut piece = commands.spawn((
+        FurniturePiece(furniture.kind),
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(furniture.rect.center().extend(z))
+            .with_scale(furniture.rect.size().extend(1.0)),
+        Mesh2d(game_assets.wall_mesh.clone()),
+        MeshMaterial2d(material),
+    ));
+    if furniture.kind.blocking() {
+        piece.insert((
+            RigidBody::Static,
+            Collider::rectangle(1.0, 1.0),
+            CollisionLayers::new(GameLayer::Default, LayerMask::ALL),
+        ));
+    }
+    piece.id()
+}
@@ -0,0 +1,91 @@
+pub mod achievements;
+pub mod arena;
+pub mod boss;
+pub mod bot;
+pub mod camera;
+pub mod campaign;
+pub mod character;
+pub mod daily;
+pub mod day;
+pub mod difficulty;
+#[cfg(feature = "dev")]
+pub mod editor;
+pub mod effects;
+pub mod elite;
+pub mod enemy;
+pub mod game_assets;
+pub mod headless;
+pub mod journal;
+pub mod layout;
+pub mod narrative;
+pub mod night;
+pub mod pickup;
+pub mod player;
+pub mod replay;
+pub mod scores;
+pub mod sleep;
+pub mod spatial;
+pub mod stats;
+pub mod timed_entity;
+
+use avian2d::prelude::PhysicsLayer;
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+#[derive(PhysicsLayer, Default)]
+enum GameLayer {
+    #[default]
+    Default,
+    Player,
+    Enemy,
+    EnemyProjectile,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, States)]
+pub enum GameState {
+    #[default]
+    DayTime,
+    NightTime,
+    GameOver,
+    GameWon,
+    /// Spending dreams between runs.
+    Journal,
+}
+
+/// The colour behind everything, outside of nightmares.
+pub const BACKGROUND_COLOR: Color = Color::srgb(0.05, 0.05, 0.1);
+
+/// All of the game's own plugins. Windowing, rendering, physics and the other
+/// third party plugins are added separately.
+pub struct GamePlugins;
+
+impl PluginGroup for GamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(character::CharacterControllerPlugin)
+            .add(timed_entity::TimedEntityPlugin)
+            .add(spatial::SpatialIndexPlugin)
+            .add(game_assets::GameAssetsPlugin)
+            .add(effects::EffectsPlugin)
+            .add(player::PlayerPlugin)
+            .add(enemy::EnemyPlugin)
+            .add(elite::ElitePlugin)
+            .add(boss::BossPlugin)
+            .add(pickup::PickupPlugin)
+            .add(arena::ArenaPlugin)
+            .add(layout::LayoutPlugin)
+            .add(camera::CameraPlugin)
+            .add(campaign::CampaignPlugin)
+            .add(night::NightPlugin)
+            .add(sleep::SleepPhasePlugin)
+            .add(day::DayPlugin)
+            .add(narrative::NarrativePlugin)
+            .add(journal::JournalPlugin)
+            .add(achievements::AchievementsPlugin)
+            .add(scores::ScoresPlugin)
+            .add(daily::DailyPlugin)
+            .add(difficulty::DifficultyPlugin)
+            .add(stats::RunStatsPlugin)
+            .add(bot::BotPlugin)
+            .add(replay::ReplayPlugin)
+    }
+}
@@ -1,11 +1,3 @@
-pub mod character;
-pub mod day;
-pub mod effects;
-pub mod enemy;
-pub mod night;
-pub mod player;
-pub mod timed_entity;
-
 use avian2d::prelude::*;
 use bevy::{
     asset::AssetMetaCheck,
@@ -13,22 +5,16 @@ use bevy::{
     prelude::*,
 };
 use bevy_hanabi::HanabiPlugin;
-use character::CharacterControllerPlugin;
-use day::DayPlugin;
-use effects::EffectsPlugin;
-use enemy::EnemyPlugin;
-use night::NightPlugin;
-use player::PlayerPlugin;
-use timed_entity::TimedEntityPlugin;
 use vleue_navigator::VleueNavigatorPlugin;
 
-#[derive(PhysicsLayer, Default)]
-enum GameLayer {
-    #[default]
-    Default,
-    Player,
-    Enemy,
-}
+use a_bad_nights_sleep::{
+    camera::CameraController,
+    daily::Mutators,
+    difficulty::Difficulty,
+    night::LevelName,
+    replay::{Recorder, Recording, Replay},
+    GamePlugins, GameState, BACKGROUND_COLOR,
+};
 
 fn main() {
     let mut app = App::new();
@@ -40,39 +26,59 @@ fn main() {
         PhysicsPlugins::default().with_length_unit(20.0),
         VleueNavigatorPlugin,
         HanabiPlugin,
-        CharacterControllerPlugin,
-        TimedEntityPlugin,
-        EffectsPlugin,
-        PlayerPlugin,
-        EnemyPlugin,
-        NightPlugin,
-        DayPlugin,
+        GamePlugins,
     ))
     .init_state::<GameState>()
     .enable_state_scoped_entities::<GameState>()
-    .insert_resource(ClearColor(Color::srgb(0.05, 0.05, 0.1)))
+    .insert_resource(ClearColor(BACKGROUND_COLOR))
     .add_systems(Startup, game_setup);
 
     #[cfg(feature = "dev")]
-    app.add_plugins(avian2d::debug_render::PhysicsDebugPlugin::default())
+    {
+        app.add_plugins((
+            avian2d::debug_render::PhysicsDebugPlugin::default(),
+            a_bad_nights_sleep::editor::EditorPlugin,
+        ))
         .add_systems(Update, print_collisions);
 
+        // `cargo run -- --level <name>` plays `assets/levels/<name>.level.ron`.
+        if let Some(name) = arg_value("--level") {
+            app.insert_resource(LevelName(Some(name)));
+        }
+    }
+
+    if let Some(path) = arg_value("--record") {
+        app.insert_resource(Recorder::new(path));
+    }
+    if let Some(path) = arg_value("--replay") {
+        let recording = match Recording::load(path.as_ref()) {
+            Ok(recording) => recording,
+            Err(err) => {
+                eprintln!("Failed to load recording {path}: {err}");
+                std::process::exit(1);
+            }
+        };
+        app.insert_resource(LevelName(recording.level.clone()))
+            .insert_resource(Mutators(recording.mutators.clone()))
+            .insert_resource(recording.difficulty.clone())
+            .insert_resource(Replay::new(recording));
+    }
+
     app.run();
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, States)]
-pub enum GameState {
-    #[default]
-    DayTime,
-    NightTime,
-    GameOver,
-    GameWon,
+/// The value following `flag` on the command line.
+fn arg_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args();
+    args.position(|arg| arg == flag)?;
+    args.next()
 }
 
 fn game_setup(mut commands: Commands) {
     // Camera
     commands.spawn((
         Camera2d,
+        CameraController::default(),
         Camera {
             hdr: true,
             ..default()
@@ -0,0 +1,327 @@
+//! The text of each day, picked from vignettes in `assets/narrative.ron` by how
+//! the run is going. Some vignettes offer a choice that changes the player's stats.
+//! When none fit, or the pool hasn't loaded yet, the campaign's text is used.
+
+use bevy::{color::palettes::tailwind, prelude::*};
+use bevy_common_assets::ron::RonAssetPlugin;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign::Campaign,
+    day::{button_hover_effect_out, button_hover_effect_over, new_day, spawn_menus},
+    difficulty::Difficulty,
+    player::PlayerStats,
+    stats::RunStats,
+    GameState,
+};
+
+pub struct NarrativePlugin;
+
+impl Plugin for NarrativePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<NarrativePool>::new(&["narrative.ron"]));
+        app.add_event::<ChoiceMade>();
+        app.init_resource::<Narrative>();
+        app.init_resource::<TodaysVignette>();
+        app.add_systems(
+            OnEnter(GameState::DayTime),
+            choose_vignette.after(new_day).before(spawn_menus),
+        );
+        app.add_systems(Update, update_choices.run_if(in_state(GameState::DayTime)));
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Condition {
+    /// Only on this day.
+    Day(u32),
+    /// Only on the last day of the run.
+    FinalDay,
+    /// Woke up early last night.
+    DiedLastNight,
+    /// Slept through the whole of last night.
+    SleptLastNight,
+    /// Earned at least this much rest last night.
+    RestAtLeast(u32),
+    /// Earned less than this much rest last night.
+    RestBelow(u32),
+    /// Bought this upgrade earlier in the run.
+    Owns(String),
+    Not(Box<Condition>),
+}
+
+/// A change to the player's stats, from making a choice.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChoiceEffect {
+    Rest(i32),
+    Comfort(f32),
+    Warmth(f32),
+    Hydration(f32),
+    SleepDuration(f32),
+    SleepIntensity(f32),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Choice {
+    pub text: String,
+    pub effects: Vec<ChoiceEffect>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Vignette {
+    /// Shown one under the other.
+    pub lines: Vec<String>,
+    /// All of them must hold for the vignette to be told.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// When several vignettes fit, one with the highest priority is told.
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    /// Whether the campaign's text for the day comes before the lines.
+    #[serde(default)]
+    pub campaign_text: bool,
+}
+
+#[derive(Asset, TypePath, Deserialize)]
+pub struct NarrativePool {
+    pub vignettes: Vec<Vignette>,
+}
+
+#[derive(Resource)]
+pub struct Narrative(pub Handle<NarrativePool>);
+
+impl FromWorld for Narrative {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<AssetServer>().load("narrative.ron"))
+    }
+}
+
+/// What the vignette conditions are checked against.
+pub struct Story<'a> {
+    pub day: u32,
+    pub last_day: u32,
+    pub run_stats: &'a RunStats,
+}
+
+impl Condition {
+    pub fn is_met(&self, story: &Story) -> bool {
+        // The reports are only cleared once the first night starts, so they
+        // belong to the last run until then.
+        let nights = if story.day > 1 {
+            &story.run_stats.nights[..]
+        } else {
+            &[]
+        };
+        let last_night = nights.last();
+        match self {
+            Condition::Day(day) => story.day == *day,
+            Condition::FinalDay => story.day == story.last_day,
+            Condition::DiedLastNight => last_night.is_some_and(|night| night.died),
+            Condition::SleptLastNight => last_night.is_some_and(|night| !night.died),
+            Condition::RestAtLeast(rest) => {
+                last_night.is_some_and(|night| night.rest_earned >= *rest)
+            }
+            Condition::RestBelow(rest) => last_night.is_some_and(|night| night.rest_earned < *rest),
+            Condition::Owns(name) => nights
+                .iter()
+                .any(|night| night.upgrades.contains(&name.as_str())),
+            Condition::Not(condition) => !condition.is_met(story),
+        }
+    }
+}
+
+impl ChoiceEffect {
+    pub fn apply(self, player_stats: &mut PlayerStats) {
+        match self {
+            ChoiceEffect::Rest(rest) => {
+                player_stats.rest = player_stats.rest.saturating_add_signed(rest);
+            }
+            ChoiceEffect::Comfort(comfort) => {
+                player_stats.comfort = (player_stats.comfort + comfort).max(1.0);
+            }
+            ChoiceEffect::Warmth(warmth) => player_stats.warmth += warmth,
+            ChoiceEffect::Hydration(hydration) => player_stats.hydration += hydration,
+            ChoiceEffect::SleepDuration(duration) => player_stats.sleep_duration += duration,
+            ChoiceEffect::SleepIntensity(intensity) => player_stats.sleep_intensity += intensity,
+        }
+    }
+}
+
+impl NarrativePool {
+    /// The vignette to tell, if any fit. Ties are broken with `seed`, so the same
+    /// run tells the same story.
+    pub fn choose(&self, story: &Story, seed: u64) -> Option<&Vignette> {
+        let fitting: Vec<_> = self
+            .vignettes
+            .iter()
+            .filter(|vignette| vignette.conditions.iter().all(|c| c.is_met(story)))
+            .collect();
+        let priority = fitting.iter().map(|vignette| vignette.priority).max()?;
+        let best: Vec<_> = fitting
+            .into_iter()
+            .filter(|vignette| vignette.priority == priority)
+            .collect();
+
+        let mut rng = fastrand::Rng::with_seed(seed.wrapping_add(story.day as u64));
+        Some(best[rng.usize(..best.len())])
+    }
+}
+
+/// Sent when the player makes a choice, with what it did to their stats.
+#[derive(Event)]
+pub struct ChoiceMade {
+    pub effects: Vec<ChoiceEffect>,
+}
+
+/// The text shown in today's menu.
+#[derive(Resource, Default)]
+pub struct TodaysVignette {
+    pub lines: Vec<String>,
+    pub choices: Vec<Choice>,
+    /// The choice made today, if any. Only one can be made.
+    pub chosen: Option<usize>,
+}
+
+fn choose_vignette(
+    mut todays_vignette: ResMut<TodaysVignette>,
+    narrative: Res<Narrative>,
+    pools: Res<Assets<NarrativePool>>,
+    campaign: Res<Campaign>,
+    difficulty: Res<Difficulty>,
+    player_stats: Res<PlayerStats>,
+    run_stats: Res<RunStats>,
+) {
+    let story = Story {
+        day: player_stats.day,
+        last_day: campaign.last_day(&difficulty),
+        run_stats: &run_stats,
+    };
+
+    let campaign_text = &campaign.day(player_stats.day, &difficulty).text;
+    *todays_vignette = match pools
+        .get(&narrative.0)
+        .and_then(|pool| pool.choose(&story, player_stats.seed))
+    {
+        Some(vignette) => TodaysVignette {
+            lines: vignette
+                .campaign_text
+                .then(|| campaign_text.clone())
+                .into_iter()
+                .chain(vignette.lines.iter().cloned())
+                .collect(),
+            choices: vignette.choices.clone(),
+            chosen: None,
+        },
+        None => TodaysVignette {
+            lines: vec![campaign_text.clone()],
+            ..default()
+        },
+    };
+}
+
+#[derive(Component)]
+struct ChoiceButton(usize);
+
+/// Today's vignette and its choices, for the day menu.
+pub(crate) fn spawn_vignette(
+    menu: &mut ChildBuilder,
+    todays_vignette: &TodaysVignette,
+    day: u32,
+    color: Color,
+) {
+    menu.spawn((
+        Node {
+            flex_direction: FlexDirection::Column,
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        PickingBehavior::IGNORE,
+    ))
+    .with_children(|vignette| {
+        for (i, line) in todays_vignette.lines.iter().enumerate() {
+            let text = if i == 0 {
+                format!("{line} (day {day})")
+            } else {
+                line.clone()
+            };
+            vignette.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(color),
+            ));
+        }
+
+        for (i, choice) in todays_vignette.choices.iter().enumerate() {
+            vignette
+                .spawn((
+                    ChoiceButton(i),
+                    Node {
+                        margin: UiRect::top(Val::Px(5.0)),
+                        ..default()
+                    },
+                    Text::new(format!("> {}", choice.text)),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    BackgroundColor(Color::NONE),
+                ))
+                .observe(button_hover_effect_over)
+                .observe(button_hover_effect_out)
+                .observe(make_choice);
+        }
+    });
+}
+
+fn make_choice(
+    trigger: Trigger<Pointer<Click>>,
+    mut todays_vignette: ResMut<TodaysVignette>,
+    mut player_stats: ResMut<PlayerStats>,
+    buttons: Query<&ChoiceButton>,
+    mut choice_made_writer: EventWriter<ChoiceMade>,
+) {
+    if trigger.button != PointerButton::Primary || todays_vignette.chosen.is_some() {
+        return;
+    }
+
+    let Ok(ChoiceButton(i)) = buttons.get(trigger.entity()) else {
+        return;
+    };
+    let Some(choice) = todays_vignette.choices.get(*i) else {
+        return;
+    };
+
+    for effect in &choice.effects {
+        effect.apply(&mut player_stats);
+    }
+    choice_made_writer.send(ChoiceMade {
+        effects: choice.effects.clone(),
+    });
+    todays_vignette.chosen = Some(*i);
+}
+
+/// Greys out the choices that weren't made.
+fn update_choices(
+    todays_vignette: Res<TodaysVignette>,
+    mut buttons: Query<(&ChoiceButton, &mut TextColor)>,
+) {
+    if !todays_vignette.is_changed() {
+        return;
+    }
+    let Some(chosen) = todays_vignette.chosen else {
+        return;
+    };
+
+    for (ChoiceButton(i), mut color) in buttons.iter_mut() {
+        color.0 = if *i == chosen {
+            tailwind::YELLOW_200.into()
+        } else {
+            tailwind::GRAY_500.into()
+        };
+    }
+}
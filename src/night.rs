@@ -1,11 +1,19 @@
-use bevy::{math::vec2, prelude::*, time::Stopwatch};
+use avian2d::prelude::{Collider, CollisionLayers, RigidBody};
+use bevy::{prelude::*, time::Stopwatch};
 use fastrand::Rng;
+use serde::Serialize;
 use vleue_navigator::NavMesh;
 
 use crate::{
-    enemy::{Enemy, EnemyRules, EnemySpawner, EnemyType},
+    arena::Arena,
+    difficulty::Difficulty,
+    elite::EliteRules,
+    enemy::{EnemyHealth, EnemyRules, EnemySpawner, EnemyType},
+    game_assets::{GameAssets, SPAWNER_SIZE},
+    layout::{FixedSpawner, LevelFile, LevelLayout},
     player::PlayerStats,
-    GameState,
+    sleep::SleepPhase,
+    GameLayer, GameState,
 };
 
 pub struct NightPlugin;
@@ -14,17 +22,44 @@ pub struct NightPlugin;
 pub struct LevelState {
     pub timer: Stopwatch,
     pub last_spawn: f32,
+    pub nightmare_until: f32,
+    /// Whether the nests from a hand-authored level have been placed.
+    pub fixed_spawners_placed: bool,
 }
 
+impl LevelState {
+    pub fn in_nightmare(&self) -> bool {
+        self.timer.elapsed_secs() < self.nightmare_until
+    }
+}
+
+/// Average number of seconds between nightmares.
+const NIGHTMARE_INTERVAL: f32 = 20.0;
+const NIGHTMARE_DURATION: f32 = 5.0;
+const NIGHTMARE_COLOR: Color = Color::srgb(0.15, 0.02, 0.05);
+
+/// The background colour from before the night, put back after nightmares and
+/// when the night ends.
+#[derive(Resource)]
+struct CalmClearColor(Color);
+
 impl Plugin for NightPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Level>();
-        app.add_systems(OnEnter(GameState::NightTime), load_level);
+        app.init_resource::<LevelName>();
+        app.init_resource::<GameRng>();
+        app.add_event::<WokeUp>();
+        app.add_systems(
+            OnEnter(GameState::NightTime),
+            (load_level, save_clear_color),
+        );
+        app.add_systems(OnExit(GameState::NightTime), restore_clear_color);
 
         app.add_systems(
-            Update,
-            (level_time, spawn_enemies).run_if(in_state(GameState::NightTime)),
+            FixedUpdate,
+            (level_time, spawn_enemies, nightmares).run_if(in_state(GameState::NightTime)),
         );
+        app.add_systems(FixedLast, stop_ticking_on_state_change);
     }
 }
 
@@ -32,80 +67,270 @@ impl Plugin for NightPlugin {
 pub struct Level {
     pub navmesh: Handle<NavMesh>,
     pub rules: Handle<EnemyRules>,
+    pub elites: Handle<EliteRules>,
+    pub arena: Handle<Arena>,
+    /// The hand-authored level being played, if any.
+    pub file: Option<Handle<LevelFile>>,
+}
+
+/// Randomness for everything that happens during the night. It's seeded from the
+/// run's seed and the day, so a night plays out the same way given the same inputs.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(pub Rng);
+
+impl GameRng {
+    pub fn for_night(seed: u64, day: u32) -> Self {
+        Self(Rng::with_seed(
+            seed ^ (day as u64).wrapping_mul(0xd1b5_4a32_d192_ed03),
+        ))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(Rng::with_seed(0))
+    }
 }
 
-fn load_level(
+/// Why the player woke up, ending the night.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum WakeCause {
+    /// Slept until the alarm went off.
+    FullNight,
+    Died,
+}
+
+impl WakeCause {
+    pub fn name(self) -> &'static str {
+        match self {
+            WakeCause::FullNight => "Full night",
+            WakeCause::Died => "Nightmares",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            WakeCause::FullNight => "You slept the whole night, but it wasn't enough",
+            WakeCause::Died => "The nightmares woke you up",
+        }
+    }
+}
+
+/// Sent when the night ends.
+#[derive(Event)]
+pub struct WokeUp(pub WakeCause);
+
+/// Name of the hand-authored level in `assets/levels` to play. When it's `None`
+/// the room is generated instead.
+#[derive(Resource, Default)]
+pub struct LevelName(pub Option<String>);
+
+pub(crate) fn load_level(
     mut commands: Commands,
+    mut fixed_time: ResMut<Time<Fixed>>,
     mut navmeshes: ResMut<Assets<NavMesh>>,
     mut level: ResMut<Level>,
     mut player_stats: ResMut<PlayerStats>,
+    level_name: Res<LevelName>,
     asset_server: Res<AssetServer>,
 ) {
     player_stats.unsafe_rest = 0;
     player_stats.died = false;
+    // Start every night on tick zero, however long the day took.
+    *fixed_time = Time::<Fixed>::from_duration(fixed_time.timestep());
+    commands.remove_resource::<LevelLayout>();
+    commands.insert_resource(GameRng::for_night(player_stats.seed, player_stats.day));
     level.navmesh = navmeshes.add(NavMesh::from_edge_and_obstacles(vec![], vec![]));
     level.rules = asset_server.load("enemies.ron");
+    level.elites = asset_server.load("elites.ron");
+    if let Some(name) = &level_name.0 {
+        // The room comes from the level file, once it has loaded.
+        level.file = Some(asset_server.load(format!("levels/{name}.level.ron")));
+        level.arena = Handle::default();
+    } else {
+        level.file = None;
+        level.arena = asset_server.load("arena.ron");
+    }
 
     commands.insert_resource(LevelState {
         timer: Stopwatch::new(),
         last_spawn: 0.0,
+        nightmare_until: 0.0,
+        fixed_spawners_placed: false,
     });
 }
 
+/// Skips the rest of the frame's ticks once a tick has asked for a new state, so
+/// the night ends on the same tick at any frame rate. The state itself changes in
+/// the usual `StateTransition` at the start of the next frame, outside the fixed loop.
+fn stop_ticking_on_state_change(
+    next_state: Res<NextState<GameState>>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+) {
+    if matches!(*next_state, NextState::Pending(_)) {
+        let overstep = fixed_time.overstep();
+        fixed_time.discard_overstep(overstep);
+    }
+}
+
 fn level_time(
     mut level_state: ResMut<LevelState>,
     time: Res<Time>,
     mut player_stats: ResMut<PlayerStats>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut woke_up_writer: EventWriter<WokeUp>,
 ) {
     level_state.timer.tick(time.delta());
     if level_state.timer.elapsed_secs() > player_stats.sleep_duration {
         info!("Sleep duration elapsed");
-        player_stats.unsafe_rest += level_state.timer.elapsed_secs() as u32;
+        player_stats.unsafe_rest +=
+            rest_earned(&player_stats, level_state.timer.elapsed_secs()) as u32;
+        woke_up_writer.send(WokeUp(WakeCause::FullNight));
         next_state.set(GameState::DayTime);
     }
 }
 
+/// Rest earned from sleeping for `elapsed` seconds of the night, without counting
+/// the rest picked up from enemies. Longer nights give more time to earn rest, more
+/// intense sleep earns more of it each second, and REM sleep counts double.
+pub fn rest_earned(player_stats: &PlayerStats, elapsed: f32) -> f32 {
+    SleepPhase::rest_at(elapsed, player_stats.sleep_duration) * player_stats.sleep_intensity
+}
+
+fn save_clear_color(
+    mut commands: Commands,
+    clear_color: Res<ClearColor>,
+    calm_clear_color: Option<Res<CalmClearColor>>,
+) {
+    // A replayed night that starts over may be in the middle of a nightmare.
+    if calm_clear_color.is_none() {
+        commands.insert_resource(CalmClearColor(clear_color.0));
+    }
+}
+
+fn restore_clear_color(
+    mut commands: Commands,
+    mut clear_color: ResMut<ClearColor>,
+    calm_clear_color: Option<Res<CalmClearColor>>,
+) {
+    if let Some(calm_clear_color) = calm_clear_color {
+        clear_color.0 = calm_clear_color.0;
+        commands.remove_resource::<CalmClearColor>();
+    }
+}
+
+fn nightmares(
+    mut level_state: ResMut<LevelState>,
+    mut clear_color: ResMut<ClearColor>,
+    calm_clear_color: Res<CalmClearColor>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    let cur_time = level_state.timer.elapsed_secs();
+    if !level_state.in_nightmare() && rng.f32() < time.delta_secs() / NIGHTMARE_INTERVAL {
+        info!("Nightmare started");
+        level_state.nightmare_until = cur_time + NIGHTMARE_DURATION;
+    }
+
+    clear_color.0 = if level_state.in_nightmare() {
+        NIGHTMARE_COLOR
+    } else {
+        calm_clear_color.0
+    };
+}
+
 fn spawn_enemies(
     mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    game_assets: Res<GameAssets>,
     mut level_state: ResMut<LevelState>,
+    rules: Res<Assets<EnemyRules>>,
+    layout: Option<Res<LevelLayout>>,
+    level: Res<Level>,
+    difficulty: Res<Difficulty>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let mut rng = Rng::new();
+    let Some(rules) = rules.get(&level.rules) else {
+        return;
+    };
+
+    // Wait for the room to be furnished, so nests don't end up inside the furniture.
+    let Some(layout) = layout else {
+        return;
+    };
+
+    if !level_state.fixed_spawners_placed {
+        for def in &layout.spawners {
+            let spawner = EnemySpawner {
+                spawn_rate: def.spawn_rate,
+                radius: 10.0,
+                spawn_type: def.spawn_type,
+            };
+            let nest = spawn_nest(
+                &mut commands,
+                &game_assets,
+                rules,
+                &difficulty,
+                def.position.into(),
+                spawner,
+            );
+            commands.entity(nest).insert(FixedSpawner);
+        }
+        level_state.fixed_spawners_placed = true;
+    }
+
     let cur_time = level_state.timer.elapsed_secs();
     for (t, spawn) in SPAWNS.iter() {
         if cur_time >= *t && level_state.last_spawn < *t {
-            let mesh = meshes.add(Rectangle::new(20.0, 20.0));
-            let material = materials.add(Color::srgb(1.0, 1.0, 1.0));
             for _ in 0..spawn.count {
                 let spawner = EnemySpawner {
-                    spawn_rate: spawn.spawn_rate,
+                    spawn_rate: spawn.spawn_rate * difficulty.spawn_rate,
                     radius: 10.0,
-                    spawn_type: EnemyType::default(),
+                    spawn_type: spawn.spawn_type,
                 };
 
-                let rx = rng.f32() - 0.5;
-                let ry = rng.f32() - 0.5;
-                let pos = vec2(rx * 1500.0, ry * 800.0);
-                let transform = Transform::from_translation(pos.extend(0.0));
-                commands.spawn((
-                    StateScoped(GameState::NightTime),
-                    Enemy,
+                let pos = layout.random_free_point(&mut rng, 50.0);
+                spawn_nest(
+                    &mut commands,
+                    &game_assets,
+                    rules,
+                    &difficulty,
+                    pos,
                     spawner,
-                    transform,
-                    MeshMaterial2d(material.clone()),
-                    Mesh2d(mesh.clone()),
-                ));
+                );
             }
             level_state.last_spawn = *t;
         }
     }
 }
 
+pub fn spawn_nest(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    rules: &EnemyRules,
+    difficulty: &Difficulty,
+    position: Vec2,
+    spawner: EnemySpawner,
+) -> Entity {
+    commands
+        .spawn((
+            StateScoped(GameState::NightTime),
+            EnemyType::Spawner,
+            EnemyHealth::new(rules.spawner.health * difficulty.enemy_health),
+            spawner,
+            Transform::from_translation(position.extend(0.0)),
+            Collider::rectangle(SPAWNER_SIZE, SPAWNER_SIZE),
+            CollisionLayers::new(GameLayer::Enemy, [GameLayer::Default, GameLayer::Player]),
+            RigidBody::Kinematic,
+            MeshMaterial2d(game_assets.spawner_material.clone()),
+            Mesh2d(game_assets.spawner_mesh.clone()),
+        ))
+        .id()
+}
+
 pub struct Spawn {
     spawn_rate: f32,
     count: u32,
+    spawn_type: EnemyType,
 }
 
 pub const SPAWNS: &[(f32, Spawn)] = &[
@@ -114,6 +339,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 0.3,
             count: 2,
+            spawn_type: EnemyType::Basic,
         },
     ),
     (
@@ -121,6 +347,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 0.5,
             count: 3,
+            spawn_type: EnemyType::Basic,
         },
     ),
     (
@@ -128,6 +355,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 0.6,
             count: 3,
+            spawn_type: EnemyType::Ranged,
         },
     ),
     (
@@ -135,6 +363,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 1.5,
             count: 6,
+            spawn_type: EnemyType::Basic,
         },
     ),
     (
@@ -142,6 +371,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 5.0,
             count: 2,
+            spawn_type: EnemyType::Basic,
         },
     ),
     (
@@ -149,6 +379,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 5.0,
             count: 7,
+            spawn_type: EnemyType::Basic,
         },
     ),
     (
@@ -156,6 +387,7 @@ pub const SPAWNS: &[(f32, Spawn)] = &[
         Spawn {
             spawn_rate: 5.0,
             count: 15,
+            spawn_type: EnemyType::Basic,
         },
     ),
 ];
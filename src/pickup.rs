@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::{
+    daily::Mutators,
+    game_assets::GameAssets,
+    player::{NightPlayer, PlayerStats},
+    sleep::SleepPhase,
+    timed_entity::Timed,
+    GameState,
+};
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (attract_pickups, collect_pickups)
+                .chain()
+                .run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// Seconds before an uncollected pickup disappears.
+const PICKUP_LIFETIME: f32 = 10.0;
+/// Speed at which attracted pickups fly towards the player.
+const ATTRACT_SPEED: f32 = 600.0;
+
+#[derive(Component, Clone, Copy, Debug)]
+pub enum Pickup {
+    Rest(u32),
+    Comfort(f32),
+    Magnet,
+}
+
+/// Marks a pickup that is flying towards the player.
+#[derive(Component)]
+pub struct Attracted;
+
+/// Spawns a pickup at the given position.
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    pickup: Pickup,
+    position: Vec3,
+) {
+    let material = match pickup {
+        Pickup::Rest(_) => game_assets.rest_material.clone(),
+        Pickup::Comfort(_) => game_assets.comfort_material.clone(),
+        Pickup::Magnet => game_assets.magnet_material.clone(),
+    };
+
+    commands.spawn((
+        pickup,
+        StateScoped(GameState::NightTime),
+        Timed(PICKUP_LIFETIME),
+        Transform::from_translation(position.with_z(-0.5)),
+        Mesh2d(game_assets.pickup_mesh.clone()),
+        MeshMaterial2d(material),
+    ));
+}
+
+fn attract_pickups(
+    mut pickup_query: Query<&mut Transform, (With<Pickup>, With<Attracted>)>,
+    player_query: Query<&GlobalTransform, With<NightPlayer>>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let target = player_transform.translation().truncate();
+    for mut transform in pickup_query.iter_mut() {
+        let offset = target - transform.translation.truncate();
+        let step = ATTRACT_SPEED * time.delta_secs();
+        let movement = offset.clamp_length_max(step);
+        transform.translation += movement.extend(0.0);
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    pickup_query: Query<(Entity, &Pickup, &GlobalTransform)>,
+    mut player_query: Query<(&mut NightPlayer, &GlobalTransform)>,
+    mut player_stats: ResMut<PlayerStats>,
+    phase: Res<SleepPhase>,
+    mutators: Res<Mutators>,
+) {
+    let Ok((mut player, player_transform)) = player_query.get_single_mut() else {
+        return;
+    };
+
+    let player_pos = player_transform.translation().truncate();
+    let mut magnet = false;
+    for (entity, pickup, transform) in pickup_query.iter() {
+        if transform.translation().truncate().distance(player_pos) > player_stats.pickup_radius {
+            continue;
+        }
+
+        match *pickup {
+            Pickup::Rest(amount) => {
+                player_stats.unsafe_rest += (amount as f32 * phase.rest_multiplier()) as u32;
+            }
+            Pickup::Comfort(amount) if mutators.healing() => {
+                player.health = (player.health + amount).min(player_stats.comfort);
+            }
+            Pickup::Comfort(_) => {}
+            Pickup::Magnet => magnet = true,
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if magnet {
+        for (entity, pickup, _) in pickup_query.iter() {
+            if let Pickup::Rest(_) = pickup {
+                // Use `try_insert`, the orb might have been collected or timed out this frame.
+                commands.entity(entity).try_insert(Attracted);
+            }
+        }
+    }
+}
@@ -1,23 +1,37 @@
-use avian2d::prelude::{Collider, CollisionLayers, LinearVelocity, RigidBody, Sensor};
-use bevy::{color::palettes::tailwind, prelude::*};
+use avian2d::prelude::{Collider, CollisionLayers, LinearVelocity, Position, RigidBody, Sensor};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    character::CharacterControllerBundle, enemy::Enemy, night::LevelState, GameLayer, GameState,
+    character::CharacterControllerBundle,
+    game_assets::{GameAssets, PLAYER_SHOT_RADIUS},
+    layout::LevelLayout,
+    night::{rest_earned, LevelState, WakeCause, WokeUp},
+    sleep::SleepPhase,
+    spatial::EnemyIndex,
+    GameLayer, GameState,
 };
 
 pub struct PlayerPlugin;
 
-#[derive(Resource, PartialEq, Clone)]
+#[derive(Resource, PartialEq, Clone, Serialize, Deserialize)]
 pub struct PlayerStats {
     pub comfort: f32,
     pub snug: f32,
     pub warmth: f32,
     pub hydration: f32,
     pub sleep_duration: f32,
+    /// How deeply the player sleeps. Multiplies the rest earned each second, but
+    /// nests spawn faster too.
+    pub sleep_intensity: f32,
+    pub pickup_radius: f32,
     pub rest: u32,
     pub unsafe_rest: u32,
     pub day: u32,
     pub died: bool,
+    pub boss_cleared: bool,
+    /// Seed for the run, each night's room layout is generated from it.
+    pub seed: u64,
 }
 
 impl Default for PlayerStats {
@@ -28,10 +42,14 @@ impl Default for PlayerStats {
             warmth: 0.0,
             hydration: 0.0,
             sleep_duration: 15.0,
+            sleep_intensity: 1.0,
+            pickup_radius: 30.0,
             rest: 300,
             unsafe_rest: 0,
             day: 0,
             died: false,
+            boss_cleared: false,
+            seed: fastrand::u64(..),
         }
     }
 }
@@ -46,9 +64,16 @@ pub struct NightPlayer {
 #[derive(Component)]
 pub struct PlayerShot;
 
+/// Sent whenever something hurts the player.
+#[derive(Event)]
+pub struct PlayerDamaged {
+    pub amount: f32,
+}
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerStats>();
+        app.add_event::<PlayerDamaged>();
 
         app.add_systems(
             OnEnter(GameState::NightTime),
@@ -56,9 +81,11 @@ impl Plugin for PlayerPlugin {
         );
 
         app.add_systems(
-            Update,
-            (update_hud, player_shoot, player_death).run_if(in_state(GameState::NightTime)),
+            FixedUpdate,
+            (player_shoot, keep_player_in_room, player_death)
+                .run_if(in_state(GameState::NightTime)),
         );
+        app.add_systems(Update, update_hud.run_if(in_state(GameState::NightTime)));
     }
 }
 
@@ -77,7 +104,14 @@ fn spawn_night_player(
             health: player_stats.comfort,
             last_shot: 0.0,
         },
-        CollisionLayers::new(GameLayer::Player, [GameLayer::Default, GameLayer::Enemy]),
+        CollisionLayers::new(
+            GameLayer::Player,
+            [
+                GameLayer::Default,
+                GameLayer::Enemy,
+                GameLayer::EnemyProjectile,
+            ],
+        ),
         CharacterControllerBundle::new(Collider::circle(radius)).with_movement(speed, 0.92),
         StateScoped(GameState::NightTime),
         Mesh2d(meshes.add(Circle::new(radius))),
@@ -87,10 +121,9 @@ fn spawn_night_player(
 
 fn player_shoot(
     mut commands: Commands,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    game_assets: Res<GameAssets>,
     time: Res<Time>,
-    enemies: Query<&GlobalTransform, With<Enemy>>,
+    enemy_index: Res<EnemyIndex>,
     player_stats: Res<PlayerStats>,
     mut player_query: Query<(&mut NightPlayer, &Transform)>,
 ) {
@@ -102,37 +135,22 @@ fn player_shoot(
         return;
     }
 
-    let mut closest_enemy: Option<&GlobalTransform> = None;
-    let mut closest_enemy_distance = f32::MAX;
-    for enemy in enemies.iter() {
-        let distance = enemy.translation().distance(player_transform.translation);
-        if closest_enemy.is_none() || distance < closest_enemy_distance {
-            closest_enemy = Some(enemy);
-            closest_enemy_distance = distance;
-        }
-    }
-
-    if let Some(enemy_transform) = closest_enemy {
-        let distance = enemy_transform
-            .translation()
-            .distance(player_transform.translation);
+    let player_position = player_transform.translation.truncate();
+    if let Some((_, enemy_position)) = enemy_index.nearest(player_position) {
+        let distance = enemy_position.distance(player_position);
         if distance < 100.0 + (player_stats.warmth / 10.0) {
-            let radius = 3.0;
-            let material = materials.add(Color::srgb(0.0, 0.2, 10.2));
-            let mesh = meshes.add(Circle::new(radius));
-            let direction =
-                (enemy_transform.translation() - player_transform.translation).normalize();
+            let direction = (enemy_position - player_position).normalize();
 
             commands.spawn((
                 PlayerShot,
                 Transform::from_translation(player_transform.translation),
-                Mesh2d(mesh.clone()),
-                Collider::circle(radius),
+                Mesh2d(game_assets.player_shot_mesh.clone()),
+                Collider::circle(PLAYER_SHOT_RADIUS),
                 Sensor,
                 CollisionLayers::new(GameLayer::Player, [GameLayer::Default, GameLayer::Enemy]),
                 RigidBody::Dynamic,
-                MeshMaterial2d(material.clone()),
-                LinearVelocity(direction.truncate() * 1000.0),
+                MeshMaterial2d(game_assets.player_shot_material.clone()),
+                LinearVelocity(direction * 1000.0),
             ));
         }
 
@@ -140,46 +158,101 @@ fn player_shoot(
     }
 }
 
+/// The walls keep the player in the room, so getting out is a bug. Puts the
+/// player back where the night started instead of ending it.
+fn keep_player_in_room(
+    mut player_query: Query<(&mut Position, &mut LinearVelocity), With<NightPlayer>>,
+    layout: Option<Res<LevelLayout>>,
+) {
+    let Some(layout) = layout else {
+        return;
+    };
+
+    for (mut position, mut velocity) in player_query.iter_mut() {
+        if !layout.arena.contains(position.0) {
+            error!("player escaped the room at {}", position.0);
+            position.0 = layout.player_start;
+            velocity.0 = Vec2::ZERO;
+        }
+    }
+}
+
 fn player_death(
-    player_query: Query<(&NightPlayer, &GlobalTransform)>,
+    player_query: Query<&NightPlayer>,
     mut next_state: ResMut<NextState<GameState>>,
     mut player_stats: ResMut<PlayerStats>,
+    mut woke_up_writer: EventWriter<WokeUp>,
 ) {
-    let Ok((player, transform)) = player_query.get_single() else {
+    let Ok(player) = player_query.get_single() else {
         return;
     };
 
-    if transform.translation().distance(Vec3::ZERO) > 2000.0 {
-        info!("player fell off the map");
-        player_stats.died = true;
-        next_state.set(GameState::DayTime);
+    if player.health > 0.0 {
+        return;
     }
 
-    if player.health <= 0.0 {
-        info!("player died");
-        player_stats.died = true;
-        next_state.set(GameState::DayTime);
-    }
+    let cause = WakeCause::Died;
+    info!("player woke up: {}", cause.name());
+    player_stats.died = true;
+    woke_up_writer.send(WokeUp(cause));
+    next_state.set(GameState::DayTime);
 }
 
 #[derive(Component)]
 struct HudSleepTimer;
 
+#[derive(Component)]
+struct HudSleepPhase;
+
 #[derive(Component)]
 struct HudHealth;
 
 fn spawn_hud(mut commands: Commands) {
+    // The timeline shows the phases of the night, with the part that has passed covered up.
+    commands
+        .spawn((
+            StateScoped(GameState::NightTime),
+            Node {
+                justify_self: JustifySelf::Start,
+                align_self: AlignSelf::FlexEnd,
+                height: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                ..default()
+            },
+        ))
+        .with_children(|timeline| {
+            for (start, end, phase) in SleepPhase::timeline() {
+                timeline.spawn((
+                    Node {
+                        height: Val::Percent(100.0),
+                        width: Val::Percent(100.0 * (end - start)),
+                        ..default()
+                    },
+                    BackgroundColor(phase.color()),
+                ));
+            }
+            timeline.spawn((
+                HudSleepTimer,
+                Node {
+                    position_type: PositionType::Absolute,
+                    height: Val::Percent(100.0),
+                    width: Val::Percent(0.0),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.7)),
+            ));
+        });
+
     commands.spawn((
-        HudSleepTimer,
+        HudSleepPhase,
         StateScoped(GameState::NightTime),
+        Text::new(SleepPhase::Light.name()),
         Node {
-            justify_self: JustifySelf::Start,
-            align_self: AlignSelf::FlexEnd,
-            height: Val::Px(10.0),
-            width: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(15.0),
+            right: Val::Px(5.0),
             ..default()
         },
-        BackgroundColor(tailwind::RED_700.into()),
     ));
 
     commands.spawn((
@@ -196,17 +269,25 @@ fn spawn_hud(mut commands: Commands) {
 
 fn update_hud(
     mut sleep_timer_query: Query<&mut Node, With<HudSleepTimer>>,
+    mut phase_query: Query<(&mut Text, &mut TextColor), (With<HudSleepPhase>, Without<HudHealth>)>,
     mut health_query: Query<&mut Text, With<HudHealth>>,
     level_state: Res<LevelState>,
     player_stats: Res<PlayerStats>,
+    phase: Res<SleepPhase>,
     player: Query<&NightPlayer>,
 ) {
     let Ok(mut node) = sleep_timer_query.get_single_mut() else {
         return;
     };
 
-    let percent = 100.0 * level_state.timer.elapsed_secs() / player_stats.sleep_duration;
-    node.width = Val::Percent(100.0 - percent);
+    let elapsed = level_state.timer.elapsed_secs();
+    let percent = 100.0 * elapsed / player_stats.sleep_duration;
+    node.width = Val::Percent(percent.min(100.0));
+
+    if let Ok((mut text, mut color)) = phase_query.get_single_mut() {
+        text.0 = phase.name().to_string();
+        color.0 = phase.color().lighter(0.3);
+    }
 
     let Ok(player) = player.get_single() else {
         return;
@@ -218,6 +299,6 @@ fn update_hud(
     text.0 = format!(
         "Comfort: {:.0} Rest: {}",
         player.health,
-        player_stats.unsafe_rest + level_state.timer.elapsed_secs() as u32
+        player_stats.unsafe_rest + rest_earned(&player_stats, elapsed) as u32
     );
 }
@@ -0,0 +1,478 @@
+//! Recording runs to a file and playing them back, for reproducing bug reports.
+//!
+//! A recording holds the player's stats as every night starts, which includes the
+//! seed for the night's [`GameRng`](crate::night::GameRng), and the movement sent on
+//! every tick of the night. Gameplay runs on the fixed timestep, so feeding the same
+//! movement back makes the night play out the same way again.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use avian2d::math::{Scalar, Vector2};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::Arena,
+    character::{CharacterSet, MovementAction},
+    daily::{Mutator, Mutators},
+    day::{find_upgrade, purchase, UpgradeBought},
+    difficulty::Difficulty,
+    elite::EliteRules,
+    enemy::EnemyRules,
+    layout::LevelFile,
+    narrative::{ChoiceEffect, ChoiceMade},
+    night::{load_level, LevelName},
+    player::PlayerStats,
+    stats::RunStats,
+    GameState,
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::NightTime),
+            start_recording_night
+                .before(load_level)
+                .run_if(resource_exists::<Recorder>),
+        );
+        app.add_systems(
+            OnExit(GameState::NightTime),
+            save_recording.run_if(resource_exists::<Recorder>),
+        );
+        // Before state transitions, so the purchases are in when the night starts.
+        app.add_systems(
+            PreUpdate,
+            (record_purchases, record_choices).run_if(resource_exists::<Recorder>),
+        );
+        app.add_systems(
+            FixedUpdate,
+            record_movement
+                .after(CharacterSet::Input)
+                .before(CharacterSet::Movement)
+                .run_if(in_state(GameState::NightTime).and(resource_exists::<Recorder>)),
+        );
+
+        // The recording is the only input while it plays.
+        app.configure_sets(
+            FixedUpdate,
+            CharacterSet::Input.run_if(not(resource_exists::<Replay>)),
+        );
+        app.add_systems(Startup, spawn_replay_hud.run_if(resource_exists::<Replay>));
+        app.add_systems(
+            Update,
+            (
+                start_replay_night.run_if(in_state(GameState::DayTime)),
+                replay_controls,
+                rewind_night.run_if(in_state(GameState::NightTime)),
+                update_replay_hud,
+            )
+                .chain()
+                .run_if(resource_exists::<Replay>),
+        );
+        app.add_systems(
+            FixedUpdate,
+            replay_movement
+                .before(CharacterSet::Movement)
+                .run_if(in_state(GameState::NightTime).and(resource_exists::<Replay>)),
+        );
+    }
+}
+
+/// How far the arrow keys scrub through a night.
+const SCRUB_SECONDS: f32 = 5.0;
+/// Playback speed while scrubbing to a tick.
+const SEEK_SPEED: f32 = 16.0;
+const PLAYBACK_SPEEDS: &[f32] = &[1.0, 2.0, 4.0, 8.0];
+
+/// Everything needed to play a run again.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Recording {
+    /// The hand-authored level the run was played on, if any.
+    pub level: Option<String>,
+    /// The Daily Dream mutators the run was played with.
+    #[serde(default)]
+    pub mutators: Vec<Mutator>,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    pub nights: Vec<NightRecording>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NightRecording {
+    /// The player's stats as the night started.
+    pub stats: PlayerStats,
+    /// Upgrades bought during the day before the night.
+    pub purchases: Vec<String>,
+    /// What the choices made in the day's story did.
+    #[serde(default)]
+    pub choices: Vec<ChoiceEffect>,
+    pub moves: Vec<MoveRun>,
+}
+
+/// The same movement sent on several ticks in a row.
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct MoveRun {
+    pub ticks: u32,
+    pub moves: Vec<[Scalar; 2]>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, serde_json::to_vec(self)?)
+    }
+}
+
+impl NightRecording {
+    /// Adds the movement sent on the next tick.
+    pub fn push(&mut self, moves: Vec<[Scalar; 2]>) {
+        match self.moves.last_mut() {
+            Some(run) if run.moves == moves => run.ticks += 1,
+            _ => self.moves.push(MoveRun { ticks: 1, moves }),
+        }
+    }
+
+    /// The movement sent on every tick of the night.
+    pub fn ticks(&self) -> Vec<Vec<[Scalar; 2]>> {
+        self.moves
+            .iter()
+            .flat_map(|run| std::iter::repeat_n(run.moves.clone(), run.ticks as usize))
+            .collect()
+    }
+}
+
+/// Records the current run to a file, which is rewritten after every night.
+#[derive(Resource)]
+pub struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+    purchases: Vec<String>,
+    choices: Vec<ChoiceEffect>,
+}
+
+impl Recorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            recording: Recording::default(),
+            purchases: Vec::new(),
+            choices: Vec::new(),
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+}
+
+/// Plays a recording instead of taking input from the keyboard or the autopilot.
+#[derive(Resource)]
+pub struct Replay {
+    recording: Recording,
+    /// The night that starts next.
+    next_night: usize,
+    /// Movement for every tick of the night being played.
+    moves: Vec<Vec<[Scalar; 2]>>,
+    /// Ticks played of the current night.
+    pub tick: u32,
+    /// Tick being fast-forwarded to.
+    seek: Option<u32>,
+    /// Whether the current night is being restarted to scrub backwards.
+    rewind: bool,
+    speed: f32,
+    /// Level data loaded up front, so the first night doesn't start before it's ready.
+    preload: Vec<UntypedHandle>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            next_night: 0,
+            moves: Vec::new(),
+            tick: 0,
+            seek: None,
+            rewind: false,
+            speed: 1.0,
+            preload: Vec::new(),
+        }
+    }
+
+    /// Whether every recorded night has been played.
+    pub fn finished(&self) -> bool {
+        self.next_night >= self.recording.nights.len()
+    }
+}
+
+fn start_recording_night(
+    mut recorder: ResMut<Recorder>,
+    player_stats: Res<PlayerStats>,
+    level_name: Res<LevelName>,
+    mutators: Res<Mutators>,
+    difficulty: Res<Difficulty>,
+) {
+    // A new run starts a new recording.
+    if player_stats.day <= 1 {
+        recorder.recording = Recording {
+            level: level_name.0.clone(),
+            mutators: mutators.0.clone(),
+            difficulty: difficulty.clone(),
+            nights: Vec::new(),
+        };
+    }
+
+    let purchases = std::mem::take(&mut recorder.purchases);
+    let choices = std::mem::take(&mut recorder.choices);
+    recorder.recording.nights.push(NightRecording {
+        stats: player_stats.clone(),
+        purchases,
+        choices,
+        moves: Vec::new(),
+    });
+}
+
+fn record_purchases(
+    mut recorder: ResMut<Recorder>,
+    mut upgrade_bought_reader: EventReader<UpgradeBought>,
+) {
+    for event in upgrade_bought_reader.read() {
+        recorder.purchases.push(event.name.to_string());
+    }
+}
+
+fn record_choices(mut recorder: ResMut<Recorder>, mut choice_made_reader: EventReader<ChoiceMade>) {
+    for event in choice_made_reader.read() {
+        recorder.choices.extend(&event.effects);
+    }
+}
+
+fn record_movement(
+    mut recorder: ResMut<Recorder>,
+    mut movement_event_reader: EventReader<MovementAction>,
+) {
+    let moves = movement_event_reader
+        .read()
+        .map(|MovementAction::Move(direction)| [direction.x, direction.y])
+        .collect();
+    if let Some(night) = recorder.recording.nights.last_mut() {
+        night.push(moves);
+    }
+}
+
+fn save_recording(recorder: Res<Recorder>) {
+    if let Err(err) = recorder.recording.save(&recorder.path) {
+        warn!(
+            "Failed to save recording to {}: {err}",
+            recorder.path.display()
+        );
+    }
+}
+
+fn start_replay_night(
+    mut replay: ResMut<Replay>,
+    mut player_stats: ResMut<PlayerStats>,
+    mut next_state: ResMut<NextState<GameState>>,
+    level_name: Res<LevelName>,
+    asset_server: Res<AssetServer>,
+) {
+    if replay.preload.is_empty() {
+        replay.preload = vec![
+            asset_server.load::<EnemyRules>("enemies.ron").untyped(),
+            asset_server.load::<EliteRules>("elites.ron").untyped(),
+            match &level_name.0 {
+                Some(name) => asset_server
+                    .load::<LevelFile>(format!("levels/{name}.level.ron"))
+                    .untyped(),
+                None => asset_server.load::<Arena>("arena.ron").untyped(),
+            },
+        ];
+    }
+    if !replay
+        .preload
+        .iter()
+        .all(|handle| asset_server.is_loaded_with_dependencies(handle.id()))
+    {
+        return;
+    }
+
+    if !matches!(*next_state, NextState::Unchanged) {
+        // The run was won or lost.
+        return;
+    }
+    replay.seek = None;
+
+    let replay = &mut *replay;
+    let Some(night) = replay.recording.nights.get(replay.next_night) else {
+        return;
+    };
+
+    // The stats are restored from the recording either way, but buying the same
+    // upgrades and making the same choices should have led to the same stats.
+    if replay.next_night > 0 {
+        let mut expected = player_stats.clone();
+        for effect in &night.choices {
+            effect.apply(&mut expected);
+        }
+        for name in &night.purchases {
+            if let Some(upgrade) = find_upgrade(name) {
+                purchase(&mut expected, upgrade, &replay.recording.difficulty);
+            }
+        }
+        if expected != night.stats {
+            warn!("Replay is out of sync before night {}", night.stats.day);
+        }
+    }
+    if !night.purchases.is_empty() {
+        info!("Bought {}", night.purchases.join(", "));
+    }
+
+    *player_stats = night.stats.clone();
+    replay.moves = night.ticks();
+    replay.tick = 0;
+    replay.next_night += 1;
+    next_state.set(GameState::NightTime);
+}
+
+fn replay_movement(
+    mut replay: ResMut<Replay>,
+    mut movement_event_writer: EventWriter<MovementAction>,
+) {
+    if let Some(moves) = replay.moves.get(replay.tick as usize) {
+        for &[x, y] in moves {
+            movement_event_writer.send(MovementAction::Move(Vector2::new(x, y)));
+        }
+    }
+    replay.tick += 1;
+}
+
+/// Starts the night being played over, without leaving it, so nothing that
+/// happens between nights runs.
+fn rewind_night(world: &mut World) {
+    let mut replay = world.resource_mut::<Replay>();
+    if !replay.rewind {
+        return;
+    }
+    replay.rewind = false;
+    let replay = &mut *replay;
+    let Some(night) = replay.recording.nights.get(replay.next_night - 1) else {
+        return;
+    };
+    let stats = night.stats.clone();
+    replay.moves = night.ticks();
+    replay.tick = 0;
+
+    let night_entities: Vec<Entity> = world
+        .query::<(Entity, &StateScoped<GameState>)>()
+        .iter(world)
+        .filter(|(_, scope)| scope.0 == GameState::NightTime)
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in night_entities {
+        // Children are despawned along with their parents.
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    *world.resource_mut::<PlayerStats>() = stats;
+    world.resource_mut::<RunStats>().restart_night();
+    world.run_schedule(OnEnter(GameState::NightTime));
+}
+
+fn replay_controls(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut replay: ResMut<Replay>,
+    mut time: ResMut<Time<Virtual>>,
+    fixed_time: Res<Time<Fixed>>,
+    state: Res<State<GameState>>,
+) {
+    let scrub_ticks = (SCRUB_SECONDS / fixed_time.timestep().as_secs_f32()) as u32;
+
+    if keys.just_pressed(KeyCode::Space) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyF) {
+        let next = PLAYBACK_SPEEDS
+            .iter()
+            .position(|&speed| speed == replay.speed)
+            .map_or(0, |i| (i + 1) % PLAYBACK_SPEEDS.len());
+        replay.speed = PLAYBACK_SPEEDS[next];
+    }
+
+    if *state.get() == GameState::NightTime {
+        if keys.just_pressed(KeyCode::ArrowRight) {
+            replay.seek = Some(replay.tick + scrub_ticks);
+        }
+        // Ticks can't be undone, so scrubbing back plays the night again from the start.
+        if keys.just_pressed(KeyCode::ArrowLeft) && !replay.rewind {
+            replay.seek = Some(replay.tick.saturating_sub(scrub_ticks));
+            replay.rewind = true;
+        }
+    }
+
+    match replay.seek {
+        Some(target) if replay.rewind || replay.tick < target => {
+            time.unpause();
+            time.set_relative_speed(SEEK_SPEED);
+        }
+        _ => {
+            replay.seek = None;
+            time.set_relative_speed(replay.speed);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ReplayHud;
+
+fn spawn_replay_hud(mut commands: Commands) {
+    commands.spawn((
+        ReplayHud,
+        Text::new("Replay"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(5.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_replay_hud(
+    replay: Res<Replay>,
+    time: Res<Time<Virtual>>,
+    fixed_time: Res<Time<Fixed>>,
+    state: Res<State<GameState>>,
+    mut hud_query: Query<&mut Text, With<ReplayHud>>,
+) {
+    let status = if *state.get() != GameState::NightTime && replay.finished() {
+        "end of recording".to_string()
+    } else if replay.seek.is_some() {
+        "seeking".to_string()
+    } else if time.is_paused() {
+        "paused".to_string()
+    } else {
+        format!("{}x", replay.speed)
+    };
+
+    let seconds = replay.tick as f32 * fixed_time.timestep().as_secs_f32();
+    for mut text in hud_query.iter_mut() {
+        text.0 = format!(
+            "Replay night {}/{}  {seconds:.1}s  {status}\n[Space] pause  [F] speed  [Left/Right] scrub",
+            replay.next_night,
+            replay.recording.nights.len(),
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Scoring finished runs and keeping the best of them in a local high score table.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    color::palettes::tailwind,
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign::Campaign,
+    daily::RunMode,
+    difficulty::{Difficulty, Preset},
+    journal::{load_profile_file, save_profile_file},
+    player::PlayerStats,
+    replay::Replay,
+    stats::RunStats,
+    GameState,
+};
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load(HIGH_SCORES_PATH));
+        app.add_systems(OnEnter(GameState::GameOver), record_score);
+        app.add_systems(OnEnter(GameState::GameWon), record_score);
+        app.add_systems(OnExit(GameState::GameOver), finish_name_entry);
+        app.add_systems(OnExit(GameState::GameWon), finish_name_entry);
+        app.add_systems(
+            Update,
+            (enter_name, update_name_entry)
+                .chain()
+                .run_if(resource_exists::<NameEntry>),
+        );
+    }
+}
+
+/// Where the high score table is kept.
+pub const HIGH_SCORES_PATH: &str = "high_scores.json";
+/// Number of runs kept in the table.
+pub const MAX_HIGH_SCORES: usize = 10;
+const MAX_NAME_LENGTH: usize = 12;
+
+/// The score for a finished run. Rest and kills count for it and damage against
+/// it, and winning is worth a bonus that is bigger the more days were left over.
+pub fn score(run_stats: &RunStats, won: bool, last_day: u32) -> u32 {
+    let nights = &run_stats.nights;
+    let rest: u32 = nights.iter().map(|night| night.rest_earned).sum();
+    let kills: u32 = nights.iter().map(|night| night.kills).sum();
+    let damage: f32 = nights.iter().map(|night| night.damage_taken).sum();
+    let days = nights.len() as u32;
+
+    let bonus = if won {
+        1000 + last_day.saturating_sub(days) * 250
+    } else {
+        0
+    };
+    let score = rest as f32 + kills as f32 * 10.0 - damage * 20.0 + bonus as f32;
+    score.max(0.0) as u32
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub won: bool,
+    pub days: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub difficulty: Preset,
+    /// When the run ended, as `YYYY-MM-DD`.
+    pub date: String,
+    /// The version of the game the run was played on.
+    pub build: String,
+}
+
+/// The tables in [`HighScores`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Leaderboard {
+    AllTime,
+    /// Daily Dreams, which are kept apart since their mutators change the rules.
+    Daily,
+}
+
+/// The best runs played on this machine, highest score first.
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores {
+    pub entries: Vec<ScoreEntry>,
+    #[serde(default)]
+    pub daily: Vec<ScoreEntry>,
+    /// The date of the last Daily Dream attempted, as `YYYY-MM-DD`.
+    #[serde(default)]
+    pub last_daily: Option<String>,
+    /// Where the table is saved, or `None` to keep it in memory only.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl HighScores {
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self {
+            path: Some(path.to_owned()),
+            ..load_profile_file(path)
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            save_profile_file(path, self);
+        }
+    }
+
+    pub fn board(&self, board: Leaderboard) -> &[ScoreEntry] {
+        match board {
+            Leaderboard::AllTime => &self.entries,
+            Leaderboard::Daily => &self.daily,
+        }
+    }
+
+    fn board_mut(&mut self, board: Leaderboard) -> &mut Vec<ScoreEntry> {
+        match board {
+            Leaderboard::AllTime => &mut self.entries,
+            Leaderboard::Daily => &mut self.daily,
+        }
+    }
+
+    /// Adds a run to the all time table, returning its place counting from zero,
+    /// or `None` if it didn't make the cut.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        self.insert_into(Leaderboard::AllTime, entry)
+    }
+
+    /// Adds a run to `board`, like [`HighScores::insert`].
+    pub fn insert_into(&mut self, board: Leaderboard, entry: ScoreEntry) -> Option<usize> {
+        let entries = self.board_mut(board);
+        // Ties go to the run that got there first.
+        let place = entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(entries.len());
+        if place >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        entries.insert(place, entry);
+        entries.truncate(MAX_HIGH_SCORES);
+        Some(place)
+    }
+}
+
+/// Today's date in UTC, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (year, month, day) = civil_date(seconds / 86_400);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The calendar date `days` days after 1970-01-01.
+pub fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's `civil_from_days`, for dates after the epoch.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The score of the run that just ended, and its place in the table if it made it.
+#[derive(Resource)]
+pub struct LastScore {
+    pub score: u32,
+    pub board: Leaderboard,
+    pub place: Option<usize>,
+}
+
+/// The player is typing a name for the high score at this place.
+#[derive(Resource)]
+pub struct NameEntry(pub Leaderboard, pub usize);
+
+pub(crate) fn record_score(
+    mut commands: Commands,
+    mut high_scores: ResMut<HighScores>,
+    run_stats: Res<RunStats>,
+    player_stats: Res<PlayerStats>,
+    run_mode: Res<RunMode>,
+    difficulty: Res<Difficulty>,
+    campaign: Res<Campaign>,
+    state: Res<State<GameState>>,
+    replay: Option<Res<Replay>>,
+) {
+    let won = *state.get() == GameState::GameWon;
+    let score = score(&run_stats, won, campaign.last_day(&difficulty));
+    let board = match *run_mode {
+        RunMode::Normal => Leaderboard::AllTime,
+        RunMode::Daily(_) => Leaderboard::Daily,
+    };
+    // A replayed run was put on the table when it was played.
+    let place = if replay.is_some() {
+        None
+    } else {
+        high_scores.insert_into(
+            board,
+            ScoreEntry {
+                name: String::new(),
+                score,
+                won,
+                days: run_stats.nights.len() as u32,
+                seed: player_stats.seed,
+                difficulty: difficulty.preset,
+                date: today(),
+                build: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        )
+    };
+
+    if let Some(place) = place {
+        high_scores.save();
+        commands.insert_resource(NameEntry(board, place));
+    }
+    commands.insert_resource(LastScore {
+        score,
+        board,
+        place,
+    });
+}
+
+fn enter_name(
+    mut commands: Commands,
+    mut keyboard_input_reader: EventReader<KeyboardInput>,
+    mut high_scores: ResMut<HighScores>,
+    name_entry: Res<NameEntry>,
+) {
+    let Some(entry) = high_scores.board_mut(name_entry.0).get_mut(name_entry.1) else {
+        return;
+    };
+
+    let mut done = false;
+    for input in keyboard_input_reader.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match &input.logical_key {
+            Key::Character(text) if entry.name.chars().count() < MAX_NAME_LENGTH => {
+                entry.name.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            Key::Space if entry.name.chars().count() < MAX_NAME_LENGTH => entry.name.push(' '),
+            Key::Backspace => {
+                entry.name.pop();
+            }
+            Key::Enter => done = true,
+            _ => {}
+        }
+    }
+
+    if done {
+        commands.remove_resource::<NameEntry>();
+        high_scores.save();
+    }
+}
+
+/// Saves the name typed so far when leaving the end screen without pressing enter.
+fn finish_name_entry(
+    mut commands: Commands,
+    name_entry: Option<Res<NameEntry>>,
+    high_scores: Res<HighScores>,
+) {
+    if name_entry.is_some() {
+        commands.remove_resource::<NameEntry>();
+        high_scores.save();
+    }
+}
+
+/// The name in the high score table that is being typed.
+#[derive(Component)]
+struct TypedName;
+
+fn update_name_entry(
+    high_scores: Res<HighScores>,
+    name_entry: Res<NameEntry>,
+    mut name_query: Query<&mut Text, With<TypedName>>,
+) {
+    if !high_scores.is_changed() {
+        return;
+    }
+
+    let Some(entry) = high_scores.board(name_entry.0).get(name_entry.1) else {
+        return;
+    };
+    for mut text in name_query.iter_mut() {
+        text.0 = format!("{}_", entry.name);
+    }
+}
+
+/// The score of the run and the high score table, for the end screens.
+pub fn spawn_high_scores(
+    menu: &mut ChildBuilder,
+    high_scores: &HighScores,
+    last_score: &LastScore,
+) {
+    let table = match last_score.board {
+        Leaderboard::AllTime => "on this machine",
+        Leaderboard::Daily => "among Daily Dreams",
+    };
+    let placement = match last_score.place {
+        Some(place) => format!(
+            "Score {}, number {} {table}! Type your name and press enter",
+            last_score.score,
+            place + 1
+        ),
+        None => format!(
+            "Score {}, not quite enough for the high scores {table}",
+            last_score.score
+        ),
+    };
+    menu.spawn((
+        Node {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        Text::new(placement),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+    ));
+
+    for (i, entry) in high_scores.board(last_score.board).iter().enumerate() {
+        let current = last_score.place == Some(i);
+        let name = if current {
+            format!("{}_", entry.name)
+        } else {
+            entry.name.clone()
+        };
+        let cells = [
+            format!("{}.", i + 1),
+            name,
+            entry.score.to_string(),
+            format!(
+                "{} on day {}",
+                if entry.won { "Won" } else { "Lost" },
+                entry.days
+            ),
+            entry.difficulty.name().to_string(),
+            entry.date.clone(),
+        ];
+
+        menu.spawn(Node {
+            margin: UiRect::horizontal(Val::Px(10.0)),
+            ..default()
+        })
+        .with_children(|row| {
+            for (column, cell) in cells.into_iter().enumerate() {
+                let mut cell = row.spawn((
+                    Node {
+                        width: Val::Px(if column == 0 { 30.0 } else { 110.0 }),
+                        ..default()
+                    },
+                    Text::new(cell),
+                    TextFont {
+                        font_size: 10.0,
+                        ..default()
+                    },
+                ));
+                if current {
+                    cell.insert(TextColor(tailwind::YELLOW_200.into()));
+                    if column == 1 {
+                        cell.insert(TypedName);
+                    }
+                }
+            }
+        });
+    }
+}
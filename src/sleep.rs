@@ -0,0 +1,148 @@
+use bevy::{color::palettes::tailwind, prelude::*};
+
+use crate::{
+    character::MovementMultiplier,
+    night::LevelState,
+    player::{NightPlayer, PlayerStats},
+    GameState,
+};
+
+pub struct SleepPhasePlugin;
+
+impl Plugin for SleepPhasePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SleepPhase>();
+        app.add_systems(OnEnter(GameState::NightTime), reset_sleep_phase);
+        app.add_systems(
+            FixedUpdate,
+            (update_sleep_phase, deep_sleep_movement)
+                .chain()
+                .run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// The stage of sleep the player is in, which changes as the night goes on.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SleepPhase {
+    #[default]
+    Light,
+    /// Harder to hurt, but also harder to move.
+    Deep,
+    /// Dreaming. Enemies are faster, but rest counts double.
+    Rem,
+}
+
+/// Where each phase starts, as a fraction of the night. The player drifts off,
+/// sinks into deep sleep, comes back up, and dreams until morning.
+const PHASES: &[(f32, SleepPhase)] = &[
+    (0.0, SleepPhase::Light),
+    (0.2, SleepPhase::Deep),
+    (0.55, SleepPhase::Light),
+    (0.65, SleepPhase::Rem),
+];
+
+impl SleepPhase {
+    /// The phase at `progress` through the night, from 0 to 1.
+    pub fn at(progress: f32) -> Self {
+        PHASES
+            .iter()
+            .rev()
+            .find(|(start, _)| progress >= *start)
+            .map_or(SleepPhase::Light, |&(_, phase)| phase)
+    }
+
+    /// Every phase of the night with where it starts and ends, as fractions of the night.
+    pub fn timeline() -> impl Iterator<Item = (f32, f32, SleepPhase)> {
+        PHASES.iter().enumerate().map(|(i, &(start, phase))| {
+            let end = PHASES.get(i + 1).map_or(1.0, |&(end, _)| end);
+            (start, end, phase)
+        })
+    }
+
+    /// Rest earned from just sleeping for `elapsed` seconds of a night lasting `duration`.
+    pub fn rest_at(elapsed: f32, duration: f32) -> f32 {
+        let progress = elapsed / duration;
+        Self::timeline()
+            .map(|(start, end, phase)| {
+                (progress.min(end) - start).max(0.0) * duration * phase.rest_multiplier()
+            })
+            .sum()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SleepPhase::Light => "Light sleep",
+            SleepPhase::Deep => "Deep sleep",
+            SleepPhase::Rem => "REM sleep",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            SleepPhase::Light => tailwind::SKY_700.into(),
+            SleepPhase::Deep => tailwind::INDIGO_800.into(),
+            SleepPhase::Rem => tailwind::FUCHSIA_600.into(),
+        }
+    }
+
+    pub fn damage_taken(self) -> f32 {
+        match self {
+            SleepPhase::Deep => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    pub fn player_speed(self) -> f32 {
+        match self {
+            SleepPhase::Deep => 0.7,
+            _ => 1.0,
+        }
+    }
+
+    pub fn enemy_speed(self) -> f32 {
+        match self {
+            SleepPhase::Rem => 1.3,
+            _ => 1.0,
+        }
+    }
+
+    pub fn rest_multiplier(self) -> f32 {
+        match self {
+            SleepPhase::Rem => 2.0,
+            _ => 1.0,
+        }
+    }
+}
+
+fn reset_sleep_phase(mut phase: ResMut<SleepPhase>) {
+    *phase = SleepPhase::Light;
+}
+
+fn update_sleep_phase(
+    mut phase: ResMut<SleepPhase>,
+    level_state: Res<LevelState>,
+    player_stats: Res<PlayerStats>,
+) {
+    let current = SleepPhase::at(level_state.timer.elapsed_secs() / player_stats.sleep_duration);
+    if *phase != current {
+        info!("Entering {}", current.name());
+        *phase = current;
+    }
+}
+
+fn deep_sleep_movement(
+    mut commands: Commands,
+    phase: Res<SleepPhase>,
+    player_query: Query<Entity, With<NightPlayer>>,
+) {
+    if !phase.is_changed() {
+        return;
+    }
+
+    for player in player_query.iter() {
+        commands
+            .entity(player)
+            .insert(MovementMultiplier(phase.player_speed()));
+    }
+}
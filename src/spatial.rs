@@ -0,0 +1,161 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{enemy::Enemy, GameState};
+
+pub struct SpatialIndexPlugin;
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EnemyIndex>();
+        app.add_systems(
+            FixedPreUpdate,
+            update_enemy_index.run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// Size of the grid cells used by the [`EnemyIndex`].
+const ENEMY_CELL_SIZE: f32 = 64.0;
+
+/// A uniform grid over entity positions, for nearest neighbour and radius queries.
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    min_cell: IVec2,
+    max_cell: IVec2,
+    len: usize,
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            min_cell: IVec2::MAX,
+            max_cell: IVec2::MIN,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all entries, keeping the allocated cells around for reuse.
+    pub fn clear(&mut self) {
+        for entries in self.cells.values_mut() {
+            entries.clear();
+        }
+        self.min_cell = IVec2::MAX;
+        self.max_cell = IVec2::MIN;
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((entity, position));
+        self.min_cell = self.min_cell.min(cell);
+        self.max_cell = self.max_cell.max(cell);
+        self.len += 1;
+    }
+
+    /// The closest entry to `position`, if any.
+    pub fn nearest(&self, position: Vec2) -> Option<(Entity, Vec2)> {
+        self.k_nearest(position, 1).into_iter().next()
+    }
+
+    /// The `k` closest entries to `position`, sorted by distance.
+    pub fn k_nearest(&self, position: Vec2, k: usize) -> Vec<(Entity, Vec2)> {
+        let mut found = Vec::new();
+        if k == 0 || self.is_empty() {
+            return found;
+        }
+
+        let center = self.cell(position);
+        let max_ring = (center - self.min_cell)
+            .abs()
+            .max((center - self.max_cell).abs())
+            .max_element();
+
+        let by_distance = |a: &(Entity, Vec2), b: &(Entity, Vec2)| {
+            a.1.distance_squared(position)
+                .total_cmp(&b.1.distance_squared(position))
+        };
+
+        for ring in 0..=max_ring {
+            for cell in ring_cells(center, ring) {
+                if let Some(entries) = self.cells.get(&cell) {
+                    found.extend_from_slice(entries);
+                }
+            }
+
+            // Anything in the rings we haven't searched yet is at least `ring` cells away.
+            if found.len() >= k {
+                found.sort_unstable_by(by_distance);
+                if found[k - 1].1.distance(position) <= ring as f32 * self.cell_size {
+                    found.truncate(k);
+                    return found;
+                }
+            }
+        }
+
+        found.sort_unstable_by(by_distance);
+        found.truncate(k);
+        found
+    }
+
+    /// All entries within `radius` of `position`, in no particular order.
+    pub fn within_radius(
+        &self,
+        position: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = self.cell(position - Vec2::splat(radius));
+        let max = self.cell(position + Vec2::splat(radius));
+        let radius_squared = radius * radius;
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, entry)| entry.distance_squared(position) <= radius_squared)
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+/// The cells at exactly `ring` cells (Chebyshev distance) from `center`.
+fn ring_cells(center: IVec2, ring: i32) -> impl Iterator<Item = IVec2> {
+    (-ring..=ring).flat_map(move |x| {
+        (-ring..=ring)
+            .filter(move |y| x.abs() == ring || y.abs() == ring)
+            .map(move |y| center + IVec2::new(x, y))
+    })
+}
+
+/// Positions of all enemies, rebuilt at the start of every fixed tick.
+#[derive(Resource, Deref, DerefMut)]
+pub struct EnemyIndex(pub SpatialIndex);
+
+impl Default for EnemyIndex {
+    fn default() -> Self {
+        Self(SpatialIndex::new(ENEMY_CELL_SIZE))
+    }
+}
+
+fn update_enemy_index(
+    mut index: ResMut<EnemyIndex>,
+    enemies: Query<(Entity, &GlobalTransform), With<Enemy>>,
+) {
+    index.clear();
+    for (entity, transform) in enemies.iter() {
+        index.insert(entity, transform.translation().truncate());
+    }
+}
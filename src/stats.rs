@@ -0,0 +1,249 @@
+use bevy::{color::palettes::tailwind, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    day::UpgradeBought,
+    enemy::EnemyDiedEvent,
+    night::{LevelState, WakeCause, WokeUp},
+    player::{PlayerDamaged, PlayerStats},
+    GameState,
+};
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>();
+        app.add_systems(OnEnter(GameState::NightTime), start_night_report);
+        app.add_systems(OnExit(GameState::NightTime), finish_night_report);
+        // Before state transitions, so the purchases are in when the night starts.
+        app.add_systems(PreUpdate, track_purchases);
+        app.add_systems(
+            FixedUpdate,
+            (track_damage, count_kills).run_if(in_state(GameState::NightTime)),
+        );
+    }
+}
+
+/// What happened during a single night.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NightReport {
+    pub day: u32,
+    pub sleep_duration: f32,
+    pub sleep_intensity: f32,
+    /// Seconds slept before the night ended, either by waking up or by dying.
+    pub survival_time: f32,
+    pub rest_earned: u32,
+    pub damage_taken: f32,
+    pub kills: u32,
+    pub died: bool,
+    pub woke_up: Option<WakeCause>,
+    /// Upgrades bought during the day before the night.
+    pub upgrades: Vec<&'static str>,
+    pub rest_spent: u32,
+}
+
+/// Reports for every night of the current run.
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub nights: Vec<NightReport>,
+    current: NightReport,
+    /// Shopping done since the last night started.
+    upgrades: Vec<&'static str>,
+    rest_spent: u32,
+}
+
+impl RunStats {
+    /// The report for the night being played.
+    pub fn current(&self) -> &NightReport {
+        &self.current
+    }
+
+    /// Puts back the shopping from before the night being played, so the night can
+    /// start over without losing it.
+    pub(crate) fn restart_night(&mut self) {
+        self.upgrades = std::mem::take(&mut self.current.upgrades);
+        self.rest_spent = self.current.rest_spent;
+    }
+
+    pub fn longest_survival(&self) -> f32 {
+        self.nights
+            .iter()
+            .map(|night| night.survival_time)
+            .fold(0.0, f32::max)
+    }
+}
+
+fn start_night_report(mut run_stats: ResMut<RunStats>, player_stats: Res<PlayerStats>) {
+    // The first night of a run, whether it's a new game or not.
+    if player_stats.day <= 1 {
+        run_stats.nights.clear();
+    }
+
+    run_stats.current = NightReport {
+        day: player_stats.day,
+        sleep_duration: player_stats.sleep_duration,
+        sleep_intensity: player_stats.sleep_intensity,
+        upgrades: std::mem::take(&mut run_stats.upgrades),
+        rest_spent: std::mem::take(&mut run_stats.rest_spent),
+        ..default()
+    };
+}
+
+fn track_damage(
+    mut run_stats: ResMut<RunStats>,
+    mut player_damaged_reader: EventReader<PlayerDamaged>,
+) {
+    for event in player_damaged_reader.read() {
+        run_stats.current.damage_taken += event.amount;
+    }
+}
+
+fn count_kills(
+    mut run_stats: ResMut<RunStats>,
+    mut enemy_died_event_reader: EventReader<EnemyDiedEvent>,
+) {
+    for event in enemy_died_event_reader.read() {
+        if event.killed {
+            run_stats.current.kills += 1;
+        }
+    }
+}
+
+fn track_purchases(
+    mut run_stats: ResMut<RunStats>,
+    mut upgrade_bought_reader: EventReader<UpgradeBought>,
+) {
+    for event in upgrade_bought_reader.read() {
+        run_stats.upgrades.push(event.name);
+        run_stats.rest_spent += event.cost;
+    }
+}
+
+fn finish_night_report(
+    mut run_stats: ResMut<RunStats>,
+    player_stats: Res<PlayerStats>,
+    level_state: Option<Res<LevelState>>,
+    mut woke_up_reader: EventReader<WokeUp>,
+) {
+    let mut report = std::mem::take(&mut run_stats.current);
+    report.survival_time = level_state.map_or(0.0, |level_state| {
+        level_state
+            .timer
+            .elapsed_secs()
+            .min(player_stats.sleep_duration)
+    });
+    report.rest_earned = player_stats.unsafe_rest;
+    report.died = player_stats.died;
+    report.woke_up = woke_up_reader.read().last().map(|woke_up| woke_up.0);
+    run_stats.nights.push(report);
+}
+
+/// Width of a column in the summary table.
+const COLUMN_WIDTH: f32 = 95.0;
+/// Height of the tallest bar in the summary chart.
+const CHART_HEIGHT: f32 = 80.0;
+
+/// A table and a chart of every night of the run, for the end screens.
+pub fn spawn_run_summary(menu: &mut ChildBuilder, run_stats: &RunStats) {
+    summary_row(
+        menu,
+        [
+            "Night", "Survived", "Kills", "Damage", "Rest +", "Rest -", "Woke up", "Upgrades",
+        ]
+        .map(String::from),
+    );
+    for night in &run_stats.nights {
+        summary_row(
+            menu,
+            [
+                night.day.to_string(),
+                format!("{:.1}/{}s", night.survival_time, night.sleep_duration),
+                night.kills.to_string(),
+                format!("{:.1}", night.damage_taken),
+                night.rest_earned.to_string(),
+                night.rest_spent.to_string(),
+                night.woke_up.map_or("-", WakeCause::name).to_string(),
+                night.upgrades.join(", "),
+            ],
+        );
+    }
+
+    let nights = &run_stats.nights;
+    menu.spawn((
+        Node {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        },
+        Text::new(format!(
+            "Longest sleep {:.1}s, {} kills, {:.1} damage taken, {} rest earned and {} spent",
+            run_stats.longest_survival(),
+            nights.iter().map(|night| night.kills).sum::<u32>(),
+            nights.iter().map(|night| night.damage_taken).sum::<f32>(),
+            nights.iter().map(|night| night.rest_earned).sum::<u32>(),
+            nights.iter().map(|night| night.rest_spent).sum::<u32>(),
+        )),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+    ));
+
+    // How much of each night was slept, as bars coloured by what ended it.
+    let longest_night = run_stats
+        .nights
+        .iter()
+        .map(|night| night.sleep_duration)
+        .fold(1.0, f32::max);
+    menu.spawn(Node {
+        height: Val::Px(CHART_HEIGHT),
+        align_items: AlignItems::End,
+        column_gap: Val::Px(4.0),
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        ..default()
+    })
+    .with_children(|chart| {
+        for night in &run_stats.nights {
+            let color = match night.woke_up {
+                Some(WakeCause::FullNight) => tailwind::GREEN_600,
+                Some(WakeCause::Died) => tailwind::RED_600,
+                None => tailwind::GRAY_600,
+            };
+            chart.spawn((
+                Node {
+                    width: Val::Px(20.0),
+                    height: Val::Percent(100.0 * night.survival_time / longest_night),
+                    ..default()
+                },
+                BackgroundColor(color.into()),
+            ));
+        }
+    });
+}
+
+fn summary_row(menu: &mut ChildBuilder, cells: [String; 8]) {
+    menu.spawn(Node {
+        margin: UiRect::horizontal(Val::Px(10.0)),
+        ..default()
+    })
+    .with_children(|row| {
+        for (i, cell) in cells.into_iter().enumerate() {
+            row.spawn((
+                Node {
+                    // The upgrades get whatever space is left.
+                    width: if i < 7 {
+                        Val::Px(COLUMN_WIDTH)
+                    } else {
+                        Val::Auto
+                    },
+                    ..default()
+                },
+                Text::new(cell),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+            ));
+        }
+    });
+}
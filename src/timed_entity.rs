@@ -3,11 +3,11 @@ pub use bevy::prelude::*;
 #[derive(Debug, Clone, Copy, Component)]
 pub struct Timed(pub f32);
 
-pub(crate) struct TimedEntityPlugin;
+pub struct TimedEntityPlugin;
 
 impl Plugin for TimedEntityPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, update_timed_entities);
+        app.add_systems(FixedUpdate, update_timed_entities);
     }
 }
 
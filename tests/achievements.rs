@@ -0,0 +1,88 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    achievements::{AchievementProgress, Condition, RunProgress},
+    enemy::EnemyType,
+    night::WakeCause,
+    player::PlayerStats,
+    replay::{Recording, Replay},
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+#[test]
+fn winning_after_buying_booze_doesnt_count() {
+    let progress = AchievementProgress::default();
+    let condition = Condition::WinWithout("Booze".to_string());
+    let mut run = RunProgress {
+        won: true,
+        purchases: vec!["Melatonin"],
+        ..default()
+    };
+
+    assert!(condition.is_met(&run, &progress));
+    run.purchases.push("Booze");
+    assert!(!condition.is_met(&run, &progress));
+}
+
+#[test]
+fn untouched_nights_need_a_full_night_without_damage() {
+    let progress = AchievementProgress::default();
+    let mut run = RunProgress {
+        night_end: Some(WakeCause::FullNight),
+        ..default()
+    };
+
+    assert!(Condition::UntouchedNight.is_met(&run, &progress));
+    run.night_damage = 1.0;
+    assert!(!Condition::UntouchedNight.is_met(&run, &progress));
+    run.night_damage = 0.0;
+    run.night_end = Some(WakeCause::Died);
+    assert!(!Condition::UntouchedNight.is_met(&run, &progress));
+}
+
+#[test]
+fn getting_hurt_is_tracked_from_damage_events() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let enemy = app
+        .world_mut()
+        .spawn((
+            EnemyType::Basic,
+            StateScoped(GameState::NightTime),
+            Transform::from_xyz(-700.0, -400.0, 0.0),
+        ))
+        .id();
+    let player = app.player();
+    app.collide(enemy, player);
+    app.advance(0.1);
+
+    assert_eq!(app.world().resource::<RunProgress>().night_damage, 1.0);
+}
+
+#[test]
+fn sleeping_through_the_night_unlocks_an_achievement() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.5);
+
+    let progress = app.world().resource::<AchievementProgress>();
+    assert!(progress.is_unlocked("First night"));
+}
+
+#[test]
+fn replays_dont_unlock_achievements() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut()
+        .insert_resource(Replay::new(Recording::default()));
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.5);
+
+    let progress = app.world().resource::<AchievementProgress>();
+    assert!(!progress.is_unlocked("First night"));
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    player::{NightPlayer, PlayerStats},
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+fn asset_counts(app: &TestApp) -> (usize, usize) {
+    (
+        app.world().resource::<Assets<Mesh>>().len(),
+        app.world().resource::<Assets<ColorMaterial>>().len(),
+    )
+}
+
+#[test]
+fn long_night_does_not_grow_asset_storage() {
+    let mut app = TestApp::new(PlayerStats {
+        sleep_duration: 60.0,
+        ..default()
+    });
+    app.enter(GameState::NightTime);
+
+    // Make the player survive the whole night.
+    let mut players = app.world_mut().query::<&mut NightPlayer>();
+    players.single_mut(app.world_mut()).health = f32::MAX;
+
+    // Let the first waves spawn before measuring.
+    app.advance(5.0);
+    let before = asset_counts(&app);
+
+    app.advance(45.0);
+    assert_eq!(app.state(), GameState::NightTime);
+    let after = asset_counts(&app);
+
+    assert!(
+        after.0 <= before.0,
+        "mesh count grew from {} to {}",
+        before.0,
+        after.0
+    );
+    assert!(
+        after.1 <= before.1,
+        "material count grew from {} to {}",
+        before.1,
+        after.1
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    bot::{strategy_by_name, Autopilot, STRATEGIES},
+    difficulty::{Difficulty, Preset},
+    player::PlayerStats,
+    stats::RunStats,
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+#[test]
+fn autopilot_shops_and_goes_to_bed() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut()
+        .insert_resource(Autopilot::new(strategy_by_name("coward").unwrap()));
+    app.advance(0.1);
+
+    assert_eq!(app.state(), GameState::NightTime);
+    assert!(app.stats().rest < PlayerStats::default().rest);
+}
+
+#[test]
+fn autopilot_shops_at_the_difficultys_prices() {
+    // The Extra blanket the kiter wants first costs 150, but 188 on Insomniac.
+    let mut app = TestApp::new(PlayerStats {
+        rest: 160,
+        ..default()
+    });
+    app.world_mut()
+        .insert_resource(Difficulty::preset(Preset::Insomniac));
+    app.world_mut()
+        .insert_resource(Autopilot::new(strategy_by_name("kiter").unwrap()));
+    app.advance(0.1);
+
+    assert_eq!(app.state(), GameState::NightTime);
+    assert!(app.stats().rest < 160);
+}
+
+#[test]
+fn every_strategy_plays_a_whole_night() {
+    for name in STRATEGIES {
+        let mut app = TestApp::new(PlayerStats::default());
+        app.world_mut()
+            .insert_resource(Autopilot::new(strategy_by_name(name).unwrap()));
+
+        // The first night lasts at least 15 seconds, even after shopping.
+        app.advance(1.0);
+        let sleep_duration = app.stats().sleep_duration;
+        app.advance(sleep_duration + 1.0);
+
+        let nights = &app.world().resource::<RunStats>().nights;
+        assert!(!nights.is_empty(), "{name} didn't finish the night");
+        assert!(nights[0].survival_time > 0.0);
+    }
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    campaign::{Campaign, CampaignDay, DayRule},
+    difficulty::{Difficulty, Preset},
+    player::PlayerStats,
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+fn day(text: &str, rules: &[DayRule]) -> CampaignDay {
+    CampaignDay {
+        text: text.to_string(),
+        rules: rules.to_vec(),
+    }
+}
+
+#[test]
+fn the_final_day_follows_the_difficulty() {
+    let campaign = Campaign::default();
+    let final_text = &campaign.days.last().unwrap().text;
+
+    for preset in [Preset::Cozy, Preset::Normal, Preset::Insomniac] {
+        let difficulty = Difficulty::preset(preset);
+        let last_day = campaign.last_day(&difficulty);
+        assert_eq!(
+            last_day as i32,
+            campaign.days.len() as i32 + difficulty.extra_days
+        );
+
+        let final_day = campaign.day(last_day, &difficulty);
+        assert_eq!(&final_day.text, final_text);
+        assert!(final_day.has(DayRule::Boss));
+        assert!((1..last_day).all(|day| !campaign.day(day, &difficulty).has(DayRule::Boss)));
+    }
+}
+
+#[test]
+fn shorter_campaigns_leave_out_the_days_before_the_final_one() {
+    let campaign = Campaign::default();
+    let difficulty = Difficulty {
+        extra_days: -2,
+        ..default()
+    };
+    let texts: Vec<_> = (1..=campaign.last_day(&difficulty))
+        .map(|day| campaign.day(day, &difficulty).text.as_str())
+        .collect();
+
+    let mut expected: Vec<_> = campaign.days[..3]
+        .iter()
+        .map(|day| day.text.as_str())
+        .collect();
+    expected.push(&campaign.days[5].text);
+    assert_eq!(texts, expected);
+}
+
+#[test]
+fn the_run_is_lost_after_the_last_day() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut().insert_resource(Campaign {
+        target_sleep: 60.0,
+        days: vec![day("First", &[]), day("Last", &[DayRule::ShopClosed])],
+    });
+
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.stats().day, 2);
+
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::GameOver);
+}
+
+#[test]
+fn sleeping_the_target_length_wins() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut().insert_resource(Campaign {
+        target_sleep: 15.0,
+        ..default()
+    });
+
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::GameWon);
+}
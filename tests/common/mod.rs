@@ -0,0 +1,136 @@
+//! Helpers for driving the game in tests, without a window or renderer.
+
+#![allow(dead_code)]
+
+use avian2d::prelude::{Collision, Contacts};
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput, NativeKey},
+        ButtonState,
+    },
+    prelude::*,
+    time::Stopwatch,
+};
+
+use a_bad_nights_sleep::{
+    headless::{headless_app, preload_level_assets, FRAME},
+    night::LevelState,
+    player::{NightPlayer, PlayerStats},
+    GameState,
+};
+
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    /// A headless game, with the level data loaded and the first day started.
+    pub fn new(player_stats: PlayerStats) -> Self {
+        let mut app = headless_app();
+        app.insert_resource(player_stats);
+        assert!(preload_level_assets(&mut app), "level data didn't load");
+        Self { app }
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    /// Runs the game for `seconds` of game time.
+    pub fn advance(&mut self, seconds: f32) {
+        for _ in 0..(seconds / FRAME).round() as u32 {
+            self.app.update();
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        *self.world().resource::<State<GameState>>().get()
+    }
+
+    /// Switches to `state`, running the frame that applies the transition.
+    pub fn enter(&mut self, state: GameState) {
+        self.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(state);
+        self.update();
+        assert_eq!(self.state(), state);
+    }
+
+    pub fn stats(&self) -> &PlayerStats {
+        self.world().resource::<PlayerStats>()
+    }
+
+    pub fn stats_mut(&mut self) -> Mut<PlayerStats> {
+        self.world_mut().resource_mut::<PlayerStats>()
+    }
+
+    pub fn player(&mut self) -> Entity {
+        let mut players = self
+            .world_mut()
+            .query_filtered::<Entity, With<NightPlayer>>();
+        players.single(self.world())
+    }
+
+    pub fn player_mut(&mut self) -> Mut<NightPlayer> {
+        let player = self.player();
+        self.world_mut().get_mut::<NightPlayer>(player).unwrap()
+    }
+
+    /// Skips ahead to `seconds` into the night, without simulating what happens in between.
+    pub fn skip_night_to(&mut self, seconds: f32) {
+        let mut timer = Stopwatch::new();
+        timer.set_elapsed(std::time::Duration::from_secs_f32(seconds));
+        self.world_mut().resource_mut::<LevelState>().timer = timer;
+    }
+
+    /// Holds down `key` until it's released.
+    pub fn press(&mut self, key: KeyCode) {
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    /// Presses `key` for a single frame, for controls that only react as it goes down.
+    pub fn tap(&mut self, key: KeyCode) {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            self.world_mut().send_event(KeyboardInput {
+                key_code: key,
+                logical_key: Key::Unidentified(NativeKey::Unidentified),
+                state,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+            self.update();
+        }
+    }
+
+    /// Sends a collision between two entities that started this frame, as if the
+    /// physics engine had found it.
+    pub fn collide(&mut self, entity1: Entity, entity2: Entity) {
+        self.world_mut().send_event(Collision(Contacts {
+            entity1,
+            entity2,
+            body_entity1: Some(entity1),
+            body_entity2: Some(entity2),
+            manifolds: vec![],
+            during_current_frame: true,
+            during_previous_frame: false,
+            total_normal_impulse: 0.0,
+            total_tangent_impulse: 0.0,
+        }));
+    }
+}
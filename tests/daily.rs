@@ -0,0 +1,71 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    daily::{daily_mutators, daily_seed, Mutator, Mutators, RunMode, DAILY_MUTATORS},
+    player::PlayerStats,
+    scores::HighScores,
+    GameState,
+};
+use common::TestApp;
+
+// Everyone playing on a date must get the same dream, whatever version they run,
+// so these values must never change.
+#[test]
+fn daily_seeds_are_stable() {
+    assert_eq!(daily_seed(2025, 1, 1), 12_408_260_688_627_086_601);
+    assert_eq!(daily_seed(2025, 6, 15), 15_973_442_742_232_885_233);
+    assert_eq!(daily_seed(2026, 10, 19), 5_956_274_182_541_815_163);
+}
+
+#[test]
+fn daily_mutators_are_stable() {
+    assert_eq!(
+        daily_mutators(daily_seed(2025, 1, 1)),
+        [Mutator::FastEnemies, Mutator::DoubleSpawns]
+    );
+    assert_eq!(
+        daily_mutators(daily_seed(2025, 6, 15)),
+        [Mutator::Fragile, Mutator::NoHealing]
+    );
+    assert_eq!(
+        daily_mutators(daily_seed(2026, 10, 19)),
+        [Mutator::DoubleSpawns, Mutator::NoHealing]
+    );
+}
+
+#[test]
+fn daily_mutators_are_distinct() {
+    for day in 1..=365 {
+        let mutators = daily_mutators(daily_seed(2025, 1 + day / 31, 1 + day % 31));
+        assert_eq!(mutators.len(), DAILY_MUTATORS);
+        assert_ne!(mutators[0], mutators[1]);
+    }
+}
+
+#[test]
+fn mutators_change_the_starting_stats() {
+    let mut player_stats = PlayerStats::default();
+    Mutators(vec![Mutator::Fragile, Mutator::Windfall]).apply_to_start(&mut player_stats);
+    assert_eq!(player_stats.comfort, PlayerStats::default().comfort / 2.0);
+    assert_eq!(player_stats.rest, PlayerStats::default().rest + 200);
+}
+
+#[test]
+fn daily_runs_go_on_their_own_leaderboard() {
+    let mut app = TestApp::new(PlayerStats {
+        day: 5,
+        ..PlayerStats::default()
+    });
+    app.world_mut()
+        .insert_resource(RunMode::Daily("2025-01-01".to_string()));
+    app.world_mut()
+        .insert_resource(Mutators(vec![Mutator::DoubleSpawns]));
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::GameOver);
+
+    let high_scores = app.world().resource::<HighScores>();
+    assert!(high_scores.entries.is_empty());
+    assert_eq!(high_scores.daily.len(), 1);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    boss::Boss,
+    day::{purchase, UPGRADES},
+    difficulty::{Difficulty, Preset, SETTINGS},
+    enemy::{EnemyHealth, EnemyType},
+    journal::DreamJournal,
+    player::PlayerStats,
+    GameState,
+};
+use avian2d::prelude::LinearVelocity;
+use bevy::prelude::*;
+use common::TestApp;
+
+#[test]
+fn prices_scale_with_the_difficulty() {
+    let insomniac = Difficulty::preset(Preset::Insomniac);
+    let melatonin = &UPGRADES[0];
+    let mut stats = PlayerStats::default();
+
+    assert!(purchase(&mut stats, melatonin, &insomniac));
+    assert_eq!(stats.rest, 300 - insomniac.price(melatonin.cost));
+    assert!(insomniac.price(melatonin.cost) > melatonin.cost);
+}
+
+#[test]
+fn tuning_a_setting_makes_a_custom_difficulty() {
+    let mut difficulty = Difficulty::default();
+    let days = SETTINGS
+        .iter()
+        .find(|setting| setting.name == "Extra days")
+        .unwrap();
+
+    days.adjust(&mut difficulty, 1.0);
+    assert_eq!(difficulty.preset, Preset::Custom);
+    assert_eq!(difficulty.extra_days, 1);
+
+    days.adjust(&mut difficulty, -100.0);
+    assert_eq!(difficulty.extra_days, -4);
+}
+
+#[test]
+fn new_runs_start_on_the_chosen_difficulty() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut().resource_mut::<DreamJournal>().difficulty = Difficulty::preset(Preset::Cozy);
+
+    // Start over, as if from the journal.
+    *app.stats_mut() = PlayerStats::default();
+    app.enter(GameState::Journal);
+    app.enter(GameState::DayTime);
+
+    assert_eq!(
+        *app.world().resource::<Difficulty>(),
+        Difficulty::preset(Preset::Cozy)
+    );
+    assert_eq!(app.stats().day, 1);
+    assert_eq!(app.stats().rest, 300 + 150);
+    assert_eq!(app.stats().comfort, 7.0);
+}
+
+#[test]
+fn harder_difficulties_have_fewer_days() {
+    let mut app = TestApp::new(PlayerStats {
+        day: 4,
+        ..default()
+    });
+    app.world_mut()
+        .insert_resource(Difficulty::preset(Preset::Insomniac));
+
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.stats().day, 6);
+    assert_eq!(app.state(), GameState::GameOver);
+}
+
+#[test]
+fn boss_minions_are_as_tough_as_other_enemies() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut()
+        .insert_resource(Difficulty::preset(Preset::Insomniac));
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    // A boss in its second phase, about to summon.
+    let boss_position = Vec3::new(2000.0, 2000.0, 0.0);
+    app.world_mut().spawn((
+        Boss {
+            max_health: 120.0,
+            phase: 1,
+            last_attack: -10.0,
+            attack_index: 1,
+            charging_until: 0.0,
+        },
+        EnemyType::Boss,
+        EnemyHealth::new(120.0),
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(boss_position),
+        LinearVelocity::default(),
+    ));
+    app.advance(0.1);
+
+    let mut enemies = app
+        .world_mut()
+        .query_filtered::<(&Transform, Option<&EnemyHealth>), (With<EnemyType>, Without<Boss>)>();
+    let minions: Vec<_> = enemies
+        .iter(app.world())
+        .filter(|(transform, _)| transform.translation.distance(boss_position) < 100.0)
+        .map(|(_, health)| health.map(|health| health.max))
+        .collect();
+    assert!(!minions.is_empty());
+    assert!(minions.iter().all(|&max| max == Some(2.0)));
+}
@@ -0,0 +1,389 @@
+mod common;
+
+use std::time::Duration;
+
+use a_bad_nights_sleep::{
+    day::{purchase, Upgrade, UpgradeBought, UPGRADES},
+    difficulty::Difficulty,
+    enemy::{EnemySpawner, EnemyType, SpawnTelegraph},
+    game_assets::ENEMY_RADIUS,
+    layout::LevelLayout,
+    night::{rest_earned, LevelState, WakeCause},
+    pickup::Pickup,
+    player::{PlayerShot, PlayerStats},
+    stats::RunStats,
+    GameState,
+};
+use avian2d::prelude::Position;
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use common::TestApp;
+
+fn upgrade(name: &str) -> &'static Upgrade {
+    UPGRADES
+        .iter()
+        .find(|upgrade| upgrade.name == name)
+        .unwrap()
+}
+
+/// Spawns an enemy far away from the player, so only injected collisions hit it.
+fn spawn_enemy(app: &mut TestApp) -> Entity {
+    app.world_mut()
+        .spawn((
+            EnemyType::Basic,
+            StateScoped(GameState::NightTime),
+            Transform::from_xyz(-700.0, -400.0, 0.0),
+        ))
+        .id()
+}
+
+#[test]
+fn first_day_starts_after_loading() {
+    let app = TestApp::new(PlayerStats::default());
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.stats().day, 1);
+}
+
+#[test]
+fn sleeping_a_full_minute_wins() {
+    let mut app = TestApp::new(PlayerStats {
+        sleep_duration: 60.0,
+        ..default()
+    });
+    app.enter(GameState::NightTime);
+    app.skip_night_to(60.5);
+    app.advance(0.1);
+
+    assert!(!app.stats().died);
+    assert_eq!(app.state(), GameState::GameWon);
+}
+
+#[test]
+fn running_out_of_days_loses() {
+    let mut app = TestApp::new(PlayerStats {
+        day: 5,
+        ..default()
+    });
+    assert_eq!(app.stats().day, 6);
+
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+
+    assert_eq!(app.state(), GameState::GameOver);
+}
+
+#[test]
+fn short_night_goes_back_to_the_shop() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.stats().day, 2);
+    assert_eq!(
+        app.world().resource::<RunStats>().nights[0].woke_up,
+        Some(WakeCause::FullNight)
+    );
+}
+
+#[test]
+fn nothing_happens_after_the_tick_that_ends_the_night() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+
+    // A slow frame, long enough for several ticks.
+    app.world_mut()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            0.2,
+        )));
+    app.update();
+    let timestep = app.world().resource::<Time<Fixed>>().timestep();
+    let elapsed = app.world().resource::<LevelState>().timer.elapsed();
+    assert_eq!(elapsed, Duration::from_secs_f32(15.5) + timestep);
+
+    app.update();
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.stats().day, 2);
+}
+
+#[test]
+fn nightmares_end_with_the_night() {
+    let mut app = TestApp::new(PlayerStats::default());
+    let calm = app.world().resource::<ClearColor>().0;
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    app.world_mut().resource_mut::<LevelState>().nightmare_until = 100.0;
+    app.advance(0.1);
+    assert_ne!(app.world().resource::<ClearColor>().0, calm);
+
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.world().resource::<ClearColor>().0, calm);
+}
+
+#[test]
+fn nests_next_to_the_player_keep_spawning() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let player = app.player();
+    let player_position = app.world().get::<Transform>(player).unwrap().translation;
+    app.world_mut().spawn((
+        EnemySpawner {
+            spawn_rate: 100.0,
+            radius: 10.0,
+            spawn_type: EnemyType::Basic,
+        },
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(player_position + Vec3::X * 5.0),
+    ));
+    app.advance(0.1);
+
+    let mut telegraphs = app
+        .world_mut()
+        .query_filtered::<&Transform, With<SpawnTelegraph>>();
+    let distances: Vec<f32> = telegraphs
+        .iter(app.world())
+        .map(|transform| transform.translation.distance(player_position))
+        .collect();
+    assert!(distances.iter().any(|&distance| distance < 130.0));
+    assert!(distances.iter().all(|&distance| distance > 115.0));
+}
+
+#[test]
+fn nests_by_the_wall_dont_spawn_into_it() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    // Stand just inside the middle of a wall, with a nest between the player and it.
+    let layout = app.world().resource::<LevelLayout>().clone();
+    let (a, b) = layout.arena.edges().next().unwrap();
+    let inwards = (b - a).perp().normalize();
+    let nest = a.midpoint(b) + inwards * 5.0;
+    let player = app.player();
+    let player_position = nest + inwards * 20.0;
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = player_position.extend(0.0);
+    app.world_mut().get_mut::<Position>(player).unwrap().0 = player_position;
+    app.world_mut().spawn((
+        EnemySpawner {
+            spawn_rate: 100.0,
+            radius: 1.0,
+            spawn_type: EnemyType::Basic,
+        },
+        StateScoped(GameState::NightTime),
+        Transform::from_translation(nest.extend(0.0)),
+    ));
+    app.advance(0.1);
+
+    let mut telegraphs = app
+        .world_mut()
+        .query_filtered::<&Transform, With<SpawnTelegraph>>();
+    // Only the pushed spawns, not the ones from the level's own nests.
+    let positions: Vec<Vec2> = telegraphs
+        .iter(app.world())
+        .map(|transform| transform.translation.truncate())
+        .filter(|position| position.distance(player_position) < 121.0)
+        .collect();
+    assert!(!positions.is_empty());
+    assert!(positions
+        .iter()
+        .all(|&position| layout.is_free(position, ENEMY_RADIUS)));
+}
+
+#[test]
+fn dying_ends_the_night() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.5);
+
+    app.player_mut().health = 0.0;
+    app.advance(0.1);
+
+    assert!(app.stats().died);
+    assert_eq!(app.state(), GameState::DayTime);
+    assert_eq!(app.world().resource::<RunStats>().nights.len(), 1);
+    assert!(app.world().resource::<RunStats>().nights[0].died);
+    assert_eq!(
+        app.world().resource::<RunStats>().nights[0].woke_up,
+        Some(WakeCause::Died)
+    );
+}
+
+#[test]
+fn leaving_the_room_puts_the_player_back() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.5);
+
+    let player = app.player();
+    let outside = Vec2::new(10_000.0, 10_000.0);
+    app.world_mut()
+        .get_mut::<Transform>(player)
+        .unwrap()
+        .translation = outside.extend(0.0);
+    app.world_mut().get_mut::<Position>(player).unwrap().0 = outside;
+    app.advance(0.1);
+
+    assert_eq!(app.state(), GameState::NightTime);
+    assert!(!app.stats().died);
+    let layout = app.world().resource::<LevelLayout>();
+    let position = app.world().get::<Position>(player).unwrap().0;
+    assert!(layout.arena.contains(position));
+    assert!(position.distance(layout.player_start) < 10.0);
+}
+
+#[test]
+fn shopping_is_reported_with_the_next_night() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.world_mut().send_event(UpgradeBought {
+        name: "Melatonin",
+        cost: 50,
+    });
+    app.enter(GameState::NightTime);
+    app.enter(GameState::DayTime);
+
+    let night = &app.world().resource::<RunStats>().nights[0];
+    assert_eq!(night.upgrades, ["Melatonin"]);
+    assert_eq!(night.rest_spent, 50);
+}
+
+#[test]
+fn buying_an_upgrade_spends_rest() {
+    let mut stats = PlayerStats::default();
+    let melatonin = upgrade("Melatonin");
+
+    assert!(purchase(&mut stats, melatonin, &Difficulty::default()));
+    assert_eq!(stats.rest, 300 - melatonin.cost);
+    assert_eq!(stats.sleep_duration, 20.0);
+}
+
+#[test]
+fn upgrades_cost_more_than_nothing() {
+    let mut stats = PlayerStats {
+        rest: 10,
+        ..default()
+    };
+
+    assert!(!purchase(
+        &mut stats,
+        upgrade("Extra blanket"),
+        &Difficulty::default()
+    ));
+    assert_eq!(stats.rest, 10);
+    assert_eq!(stats.warmth, 0.0);
+}
+
+#[test]
+fn sleeping_through_the_night_earns_rest() {
+    for intensity in [1.0, 2.0] {
+        let mut app = TestApp::new(PlayerStats {
+            sleep_intensity: intensity,
+            ..default()
+        });
+        app.enter(GameState::NightTime);
+        app.skip_night_to(15.5);
+        app.advance(0.1);
+
+        let expected = rest_earned(app.stats(), 15.0) as u32;
+        assert!(expected > 0);
+        assert_eq!(app.stats().unsafe_rest, expected);
+        assert_eq!(app.stats().rest, 300 + expected);
+    }
+}
+
+#[test]
+fn intense_sleep_earns_more_rest() {
+    let light = PlayerStats::default();
+    let intense = PlayerStats {
+        sleep_intensity: 1.5,
+        ..default()
+    };
+    assert!(rest_earned(&intense, 15.0) > rest_earned(&light, 15.0));
+}
+
+#[test]
+fn shooting_an_enemy_drops_rest() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let enemy = spawn_enemy(&mut app);
+    let shot = app.world_mut().spawn(PlayerShot).id();
+    app.collide(shot, enemy);
+    app.advance(0.1);
+
+    assert!(app.world().get_entity(enemy).is_err());
+    let mut pickups = app.world_mut().query::<&Pickup>();
+    assert!(pickups
+        .iter(app.world())
+        .any(|pickup| matches!(pickup, Pickup::Rest(_))));
+    assert_eq!(app.world().resource::<RunStats>().current().kills, 1);
+}
+
+#[test]
+fn touching_an_enemy_hurts() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let health = app.player_mut().health;
+    let enemy = spawn_enemy(&mut app);
+    let player = app.player();
+    app.collide(enemy, player);
+    app.advance(0.1);
+
+    assert_eq!(app.player_mut().health, health - 1.0);
+    assert_eq!(
+        app.world().resource::<RunStats>().current().damage_taken,
+        1.0
+    );
+}
+
+#[test]
+fn healing_doesnt_hide_damage() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let health = app.player_mut().health;
+    let enemy = spawn_enemy(&mut app);
+    let player = app.player();
+    app.collide(enemy, player);
+    // As if a comfort pickup was collected on the same tick.
+    app.player_mut().health += 1.0;
+    app.advance(0.1);
+
+    assert_eq!(app.player_mut().health, health);
+    assert_eq!(
+        app.world().resource::<RunStats>().current().damage_taken,
+        1.0
+    );
+}
+
+#[test]
+fn holding_a_key_moves_the_player() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.advance(0.1);
+
+    let player = app.player();
+    let start = app.world().get::<Transform>(player).unwrap().translation;
+    app.press(KeyCode::KeyD);
+    app.advance(0.5);
+    app.release(KeyCode::KeyD);
+
+    let end = app.world().get::<Transform>(player).unwrap().translation;
+    assert!(
+        end.x > start.x + 1.0,
+        "player didn't move: {start} -> {end}"
+    );
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    difficulty::{Difficulty, Preset},
+    journal::{dreams_earned, DreamJournal, JOURNAL_ENTRIES},
+    player::PlayerStats,
+    replay::{Recording, Replay},
+    stats::RunStats,
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+fn entry(name: &str) -> &'static a_bad_nights_sleep::journal::JournalEntry {
+    JOURNAL_ENTRIES
+        .iter()
+        .find(|entry| entry.name == name)
+        .unwrap()
+}
+
+#[test]
+fn unlocking_spends_dreams_once() {
+    let mut journal = DreamJournal {
+        dreams: 10,
+        ..default()
+    };
+    let static_entry = entry("Static");
+
+    assert!(journal.unlock(static_entry));
+    assert_eq!(journal.dreams, 10 - static_entry.cost);
+    assert!(!journal.unlock(static_entry));
+    assert_eq!(journal.dreams, 10 - static_entry.cost);
+}
+
+#[test]
+fn unlocked_upgrades_are_in_the_shop() {
+    let mut journal = DreamJournal {
+        dreams: 10,
+        ..default()
+    };
+    let in_shop = |journal: &DreamJournal| {
+        journal
+            .shop_upgrades()
+            .any(|upgrade| upgrade.name == "White noise machine")
+    };
+
+    assert!(!in_shop(&journal));
+    journal.unlock(entry("Static"));
+    assert!(in_shop(&journal));
+}
+
+#[test]
+fn a_lost_run_still_earns_dreams() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.enter(GameState::DayTime);
+    app.enter(GameState::GameOver);
+
+    let earned = dreams_earned(app.world().resource::<RunStats>(), false);
+    assert!(earned > 0);
+    assert_eq!(app.world().resource::<DreamJournal>().dreams, earned);
+}
+
+#[test]
+fn replays_dont_earn_dreams() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::NightTime);
+    app.enter(GameState::DayTime);
+    app.world_mut()
+        .insert_resource(Replay::new(Recording::default()));
+    app.enter(GameState::GameOver);
+
+    assert_eq!(app.world().resource::<DreamJournal>().dreams, 0);
+}
+
+#[test]
+fn new_runs_start_with_the_unlocked_rewards() {
+    let mut app = TestApp::new(PlayerStats::default());
+    app.enter(GameState::GameOver);
+
+    let mut journal = DreamJournal {
+        dreams: 100,
+        ..default()
+    };
+    journal.unlock(entry("Well rested"));
+    journal.unlock(entry("Old friend"));
+    app.world_mut().insert_resource(journal);
+
+    *app.stats_mut() = PlayerStats::default();
+    app.enter(GameState::DayTime);
+
+    let default = PlayerStats::default();
+    assert_eq!(app.stats().day, 1);
+    assert_eq!(app.stats().rest, default.rest + 100);
+    assert!(app.stats().pickup_radius > default.pickup_radius);
+}
+
+#[test]
+fn unreadable_journals_are_kept() {
+    let dir = std::env::temp_dir().join("a-bad-nights-sleep-unreadable-journal");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("profile.json");
+    std::fs::write(&path, "{ not json").unwrap();
+
+    let journal = DreamJournal::load(&path);
+    assert_eq!(journal.dreams, 0);
+    journal.save();
+
+    let kept: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".unreadable"))
+        .collect();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(std::fs::read_to_string(&kept[0]).unwrap(), "{ not json");
+}
+
+#[test]
+fn journals_from_before_extra_days_still_load() {
+    let json = r#"{
+        "dreams": 7,
+        "unlocked": [],
+        "difficulty": {
+            "preset": "Cozy",
+            "enemy_speed": 0.85,
+            "enemy_health": 1.0,
+            "spawn_rate": 0.75,
+            "prices": 0.8,
+            "extra_rest": 150,
+            "extra_comfort": 2.0,
+            "days": 2
+        }
+    }"#;
+    let journal: DreamJournal = serde_json::from_str(json).unwrap();
+    assert_eq!(journal.dreams, 7);
+    assert_eq!(journal.difficulty, Difficulty::preset(Preset::Cozy));
+}
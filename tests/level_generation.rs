@@ -0,0 +1,100 @@
+use a_bad_nights_sleep::{
+    arena::Arena,
+    layout::{FurnitureKind, LevelLayout, PLAYER_CLEARANCE},
+};
+
+/// The same L-shaped room as `assets/arena.ron`.
+fn bedroom() -> Arena {
+    Arena {
+        wall_thickness: 20.0,
+        outline: vec![
+            (-800.0, -450.0),
+            (800.0, -450.0),
+            (800.0, 250.0),
+            (500.0, 250.0),
+            (500.0, 450.0),
+            (-800.0, 450.0),
+        ],
+    }
+}
+
+#[test]
+fn same_seed_and_day_give_the_same_layout() {
+    let a = LevelLayout::generate(bedroom(), 1234, 3);
+    let b = LevelLayout::generate(bedroom(), 1234, 3);
+
+    assert_eq!(a.player_start, b.player_start);
+    assert_eq!(a.furniture.len(), b.furniture.len());
+    for (a, b) in a.furniture.iter().zip(&b.furniture) {
+        assert_eq!(a.kind, b.kind);
+        assert_eq!(a.rect, b.rect);
+    }
+}
+
+#[test]
+fn days_of_a_run_get_different_layouts() {
+    let monday = LevelLayout::generate(bedroom(), 1234, 0);
+    let tuesday = LevelLayout::generate(bedroom(), 1234, 1);
+
+    let rects = |layout: &LevelLayout| layout.furniture.iter().map(|f| f.rect).collect::<Vec<_>>();
+    assert_ne!(rects(&monday), rects(&tuesday));
+}
+
+#[test]
+fn layouts_stay_connected() {
+    for seed in 0..20 {
+        for day in 0..7 {
+            let layout = LevelLayout::generate(bedroom(), seed, day);
+            assert!(
+                layout.is_connected(PLAYER_CLEARANCE),
+                "seed {seed} day {day} has unreachable floor"
+            );
+            assert!(layout.is_free(layout.player_start, PLAYER_CLEARANCE));
+        }
+    }
+}
+
+#[test]
+fn furniture_stays_inside_the_room() {
+    for seed in 0..20 {
+        let layout = LevelLayout::generate(bedroom(), seed, 5);
+        assert!(layout
+            .furniture
+            .iter()
+            .any(|furniture| furniture.kind == FurnitureKind::Bed));
+
+        for obstacle in layout.obstacles() {
+            let rect = obstacle.rect;
+            for corner in [
+                rect.min,
+                rect.max,
+                rect.min.with_x(rect.max.x),
+                rect.max.with_x(rect.min.x),
+            ] {
+                assert!(
+                    layout.arena.contains(corner),
+                    "seed {seed}: {:?} sticks out of the room",
+                    obstacle.kind
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn obstacles_never_overlap() {
+    for seed in 0..20 {
+        let layout = LevelLayout::generate(bedroom(), seed, 6);
+        let obstacles: Vec<_> = layout.obstacles().collect();
+        for (i, a) in obstacles.iter().enumerate() {
+            for b in &obstacles[i + 1..] {
+                assert!(
+                    a.rect.intersect(b.rect).is_empty(),
+                    "seed {seed}: {:?} overlaps {:?}",
+                    a.kind,
+                    b.kind
+                );
+            }
+        }
+    }
+}
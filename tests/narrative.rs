@@ -0,0 +1,133 @@
+use a_bad_nights_sleep::{
+    narrative::{ChoiceEffect, Condition, NarrativePool, Story, Vignette},
+    player::PlayerStats,
+    stats::{NightReport, RunStats},
+};
+use bevy::prelude::*;
+
+fn vignette(line: &str, conditions: Vec<Condition>, priority: i32) -> Vignette {
+    Vignette {
+        lines: vec![line.to_string()],
+        conditions,
+        priority,
+        choices: Vec::new(),
+        campaign_text: false,
+    }
+}
+
+fn run_with(nights: Vec<NightReport>) -> RunStats {
+    let mut run_stats = RunStats::default();
+    run_stats.nights = nights;
+    run_stats
+}
+
+#[test]
+fn conditions_look_at_last_night() {
+    let run_stats = run_with(vec![
+        NightReport {
+            upgrades: vec!["Booze"],
+            rest_earned: 300,
+            ..default()
+        },
+        NightReport {
+            died: true,
+            rest_earned: 20,
+            ..default()
+        },
+    ]);
+    let story = Story {
+        day: 3,
+        last_day: 6,
+        run_stats: &run_stats,
+    };
+
+    assert!(Condition::DiedLastNight.is_met(&story));
+    assert!(!Condition::SleptLastNight.is_met(&story));
+    assert!(Condition::RestBelow(50).is_met(&story));
+    assert!(!Condition::RestAtLeast(50).is_met(&story));
+    assert!(Condition::Owns("Booze".to_string()).is_met(&story));
+    assert!(!Condition::Owns("Melatonin".to_string()).is_met(&story));
+    assert!(Condition::Not(Box::new(Condition::FinalDay)).is_met(&story));
+}
+
+#[test]
+fn the_first_day_ignores_the_last_run() {
+    let run_stats = run_with(vec![NightReport {
+        died: true,
+        upgrades: vec!["Booze"],
+        ..default()
+    }]);
+    let story = Story {
+        day: 1,
+        last_day: 6,
+        run_stats: &run_stats,
+    };
+
+    assert!(!Condition::DiedLastNight.is_met(&story));
+    assert!(!Condition::Owns("Booze".to_string()).is_met(&story));
+}
+
+#[test]
+fn the_fitting_vignette_with_the_highest_priority_is_told() {
+    let pool = NarrativePool {
+        vignettes: vec![
+            vignette("Any day", Vec::new(), 0),
+            vignette("Bad night", vec![Condition::DiedLastNight], 5),
+            vignette("Last day", vec![Condition::FinalDay], 10),
+        ],
+    };
+    let run_stats = run_with(vec![NightReport {
+        died: true,
+        ..default()
+    }]);
+    let mut story = Story {
+        day: 2,
+        last_day: 6,
+        run_stats: &run_stats,
+    };
+
+    assert_eq!(pool.choose(&story, 0).unwrap().lines, ["Bad night"]);
+    story.day = 6;
+    assert_eq!(pool.choose(&story, 0).unwrap().lines, ["Last day"]);
+    story.day = 1;
+    assert_eq!(pool.choose(&story, 0).unwrap().lines, ["Any day"]);
+
+    let empty = NarrativePool {
+        vignettes: Vec::new(),
+    };
+    assert!(empty.choose(&story, 0).is_none());
+}
+
+#[test]
+fn ties_are_broken_by_the_seed() {
+    let pool = NarrativePool {
+        vignettes: (0..8)
+            .map(|i| vignette(&i.to_string(), Vec::new(), 0))
+            .collect(),
+    };
+    let run_stats = RunStats::default();
+    let story = Story {
+        day: 2,
+        last_day: 6,
+        run_stats: &run_stats,
+    };
+
+    let told = pool.choose(&story, 42).unwrap().lines.clone();
+    assert_eq!(pool.choose(&story, 42).unwrap().lines, told);
+    assert!((0..20).any(|seed| pool.choose(&story, seed).unwrap().lines != told));
+}
+
+#[test]
+fn skipping_breakfast_trades_comfort_for_rest() {
+    let mut player_stats = PlayerStats::default();
+    for effect in [ChoiceEffect::Rest(50), ChoiceEffect::Comfort(-1.0)] {
+        effect.apply(&mut player_stats);
+    }
+    assert_eq!(player_stats.rest, PlayerStats::default().rest + 50);
+    assert_eq!(player_stats.comfort, PlayerStats::default().comfort - 1.0);
+
+    ChoiceEffect::Rest(-10_000).apply(&mut player_stats);
+    ChoiceEffect::Comfort(-100.0).apply(&mut player_stats);
+    assert_eq!(player_stats.rest, 0);
+    assert_eq!(player_stats.comfort, 1.0);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    bot::{strategy_by_name, Autopilot},
+    player::PlayerStats,
+    replay::{Recorder, Recording, Replay},
+    stats::{NightReport, RunStats},
+    GameState,
+};
+use bevy::prelude::*;
+use common::TestApp;
+
+/// Plays the first night of a run with `app`, returning its report.
+fn play_first_night(app: &mut TestApp) -> NightReport {
+    app.advance(1.0);
+    let sleep_duration = app.stats().sleep_duration;
+    app.advance(sleep_duration + 1.0);
+
+    let nights = &app.world().resource::<RunStats>().nights;
+    assert!(!nights.is_empty(), "the night didn't end");
+    nights[0].clone()
+}
+
+/// Records the first night of a run played by the autopilot.
+fn record_first_night(name: &str) -> (NightReport, Recording) {
+    let path = std::env::temp_dir().join(format!("a-bad-nights-sleep-{name}.json"));
+
+    let mut recorded = TestApp::new(PlayerStats::default());
+    recorded
+        .world_mut()
+        .insert_resource(Autopilot::new(strategy_by_name("kiter").unwrap()));
+    recorded.world_mut().insert_resource(Recorder::new(&path));
+    let original = play_first_night(&mut recorded);
+
+    let recording = Recording::load(&path).expect("the recording was saved");
+    assert!(!recording.nights.is_empty());
+    assert!(!recording.nights[0].moves.is_empty());
+    (original, recording)
+}
+
+#[test]
+fn replaying_a_recording_plays_the_same_night() {
+    let (original, recording) = record_first_night("replay-test");
+
+    // A different seed to start with, which the recording should replace.
+    let mut replayed = TestApp::new(PlayerStats {
+        seed: 1,
+        ..PlayerStats::default()
+    });
+    replayed.world_mut().insert_resource(Replay::new(recording));
+    let replay = play_first_night(&mut replayed);
+
+    assert_eq!(replay.day, original.day);
+    assert_eq!(replay.survival_time, original.survival_time);
+    assert_eq!(replay.damage_taken, original.damage_taken);
+    assert_eq!(replay.kills, original.kills);
+    assert_eq!(replay.rest_earned, original.rest_earned);
+    assert_eq!(replay.died, original.died);
+}
+
+#[test]
+fn scrubbing_back_starts_the_night_over_in_place() {
+    let (original, recording) = record_first_night("rewind-test");
+
+    let mut replayed = TestApp::new(PlayerStats::default());
+    replayed.world_mut().insert_resource(Replay::new(recording));
+    replayed.advance(3.0);
+    assert_eq!(replayed.state(), GameState::NightTime);
+    let day = replayed.stats().day;
+
+    replayed.tap(KeyCode::ArrowLeft);
+    assert_eq!(replayed.state(), GameState::NightTime);
+    assert_eq!(replayed.stats().day, day);
+    assert!(replayed.world().resource::<RunStats>().nights.is_empty());
+
+    let sleep_duration = replayed.stats().sleep_duration;
+    replayed.advance(sleep_duration + 1.0);
+    let nights = &replayed.world().resource::<RunStats>().nights;
+    assert_eq!(nights.len(), 1);
+    assert_eq!(nights[0].survival_time, original.survival_time);
+    assert_eq!(nights[0].kills, original.kills);
+    assert_eq!(nights[0].rest_earned, original.rest_earned);
+}
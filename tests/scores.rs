@@ -0,0 +1,90 @@
+mod common;
+
+use a_bad_nights_sleep::{
+    difficulty::Preset,
+    player::PlayerStats,
+    replay::{Recording, Replay},
+    scores::{civil_date, HighScores, LastScore, ScoreEntry, MAX_HIGH_SCORES},
+    GameState,
+};
+use common::TestApp;
+
+fn entry(score: u32) -> ScoreEntry {
+    ScoreEntry {
+        name: format!("run {score}"),
+        score,
+        won: false,
+        days: 6,
+        seed: 0,
+        difficulty: Preset::Normal,
+        date: "2025-01-01".to_string(),
+        build: "test".to_string(),
+    }
+}
+
+#[test]
+fn the_table_keeps_the_best_runs_in_order() {
+    let mut high_scores = HighScores::default();
+    for score in 0..MAX_HIGH_SCORES as u32 {
+        assert!(high_scores.insert(entry(score * 100)).is_some());
+    }
+
+    assert_eq!(high_scores.insert(entry(50)), Some(MAX_HIGH_SCORES - 1));
+    assert_eq!(high_scores.insert(entry(0)), None);
+    assert_eq!(high_scores.insert(entry(10_000)), Some(0));
+    assert_eq!(high_scores.entries.len(), MAX_HIGH_SCORES);
+    assert!(high_scores
+        .entries
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn ties_go_to_the_earlier_run() {
+    let mut high_scores = HighScores::default();
+    high_scores.insert(entry(100));
+    assert_eq!(high_scores.insert(entry(100)), Some(1));
+}
+
+#[test]
+fn dates_are_counted_from_the_epoch() {
+    assert_eq!(civil_date(0), (1970, 1, 1));
+    assert_eq!(civil_date(59), (1970, 3, 1));
+    assert_eq!(civil_date(11_016), (2000, 2, 29));
+    assert_eq!(civil_date(19_723), (2024, 1, 1));
+}
+
+#[test]
+fn a_finished_run_is_scored_and_placed() {
+    let mut app = TestApp::new(PlayerStats {
+        day: 5,
+        ..PlayerStats::default()
+    });
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::GameOver);
+
+    let last_score = app.world().resource::<LastScore>();
+    assert_eq!(last_score.place, Some(0));
+    let high_scores = app.world().resource::<HighScores>();
+    assert_eq!(high_scores.entries[0].score, last_score.score);
+    assert_eq!(high_scores.entries[0].seed, app.stats().seed);
+}
+
+#[test]
+fn replays_are_not_scored() {
+    let mut app = TestApp::new(PlayerStats {
+        day: 5,
+        ..PlayerStats::default()
+    });
+    app.world_mut()
+        .insert_resource(Replay::new(Recording::default()));
+    app.enter(GameState::NightTime);
+    app.skip_night_to(15.5);
+    app.advance(0.1);
+    assert_eq!(app.state(), GameState::GameOver);
+
+    assert_eq!(app.world().resource::<LastScore>().place, None);
+    assert!(app.world().resource::<HighScores>().entries.is_empty());
+}